@@ -4,6 +4,12 @@ use crate::{expr::*, token_type::TokenType};
 
 pub struct AstPrinter;
 
+impl Default for AstPrinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AstPrinter {
     pub fn new() -> Self {
         AstPrinter
@@ -43,7 +49,7 @@ impl AstPrinter {
 
     pub fn print_variable_expr(&self, expr: Variable) -> String {
         let expression = expr.get_value();
-        return String::from(expression.get_lexeme());
+        return expression.get_lexeme();
     }
 
     fn get_new_print(&self, expr: Expr) -> String {
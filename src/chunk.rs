@@ -0,0 +1,206 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::token::Span;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(u8)]
+pub enum OpCode {
+    // Operand: 2-byte constant index.
+    Constant,
+    Nil,
+    True,
+    False,
+    Pop,
+    // Operand: 1-byte count of values to copy from the top of the stack.
+    Duplicate,
+    // Operand: 1-byte distance below the top of the slot to overwrite with the top value.
+    SetBelow,
+
+    // Operand: 1-byte stack slot.
+    GetLocal,
+    SetLocal,
+    // Operand: 2-byte constant index holding the name.
+    GetGlobal,
+    DefineGlobal,
+    SetGlobal,
+    // Operand: 1-byte upvalue index.
+    GetUpvalue,
+    SetUpvalue,
+
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    FloorDivide,
+    Power,
+    Not,
+    Negate,
+
+    // Operand: 2-byte element count, taken from the top of the stack.
+    BuildList,
+    // Operand: 2-byte entry count, taken as key/value pairs from the top of the stack.
+    BuildMap,
+    GetIndex,
+    SetIndex,
+
+    Print,
+
+    // Operand: 2-byte forward or backward jump distance.
+    Jump,
+    JumpIfFalse,
+    Loop,
+
+    // Operand: 1-byte argument count.
+    Call,
+    // Operand: 2-byte constant index, then one (is_local, index) byte pair per upvalue.
+    Closure,
+    CloseUpvalue,
+    Return,
+
+    // Operand: 2-byte constant index holding the class name.
+    Class,
+    // Copies the superclass's methods into the subclass on top of it.
+    Inherit,
+    // Operand: 2-byte constant index holding the name of a property or method.
+    Method,
+    GetProperty,
+    SetProperty,
+    GetSuper,
+    // Operand: 2-byte constant index holding the method name, then a 1-byte argument count.
+    Invoke,
+    SuperInvoke,
+}
+
+impl OpCode {
+    pub fn from_byte(byte: u8) -> Option<OpCode> {
+        // Same order as the declaration above, so that `OPCODES[op as usize] == op`.
+        const OPCODES: [OpCode; 49] = [
+            OpCode::Constant, OpCode::Nil, OpCode::True, OpCode::False, OpCode::Pop,
+            OpCode::Duplicate, OpCode::SetBelow,
+            OpCode::GetLocal, OpCode::SetLocal,
+            OpCode::GetGlobal, OpCode::DefineGlobal, OpCode::SetGlobal,
+            OpCode::GetUpvalue, OpCode::SetUpvalue,
+            OpCode::Equal, OpCode::NotEqual, OpCode::Greater, OpCode::GreaterEqual, OpCode::Less, OpCode::LessEqual,
+            OpCode::Add, OpCode::Subtract, OpCode::Multiply, OpCode::Divide,
+            OpCode::Modulo, OpCode::FloorDivide, OpCode::Power, OpCode::Not, OpCode::Negate,
+            OpCode::BuildList, OpCode::BuildMap, OpCode::GetIndex, OpCode::SetIndex,
+            OpCode::Print,
+            OpCode::Jump, OpCode::JumpIfFalse, OpCode::Loop,
+            OpCode::Call, OpCode::Closure, OpCode::CloseUpvalue, OpCode::Return,
+            OpCode::Class, OpCode::Inherit, OpCode::Method,
+            OpCode::GetProperty, OpCode::SetProperty, OpCode::GetSuper,
+            OpCode::Invoke, OpCode::SuperInvoke,
+        ];
+
+        OPCODES.get(byte as usize).copied()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Constant {
+    Number(f64),
+    String(String),
+    Function(Rc<BytecodeFunction>),
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Constant::Number(number) => write!(f, "{}", number),
+            Constant::String(string) => write!(f, "{}", string),
+            Constant::Function(function) => write!(f, "<fn {}>", function.name),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BytecodeFunction {
+    pub name: String,
+    pub arity: usize,
+    pub upvalue_count: usize,
+    pub chunk: Chunk,
+}
+
+impl BytecodeFunction {
+    pub fn new(name: String) -> Self {
+        BytecodeFunction {
+            name,
+            arity: 0,
+            upvalue_count: 0,
+            chunk: Chunk::new(),
+        }
+    }
+}
+
+// A run of bytecode that starts at `offset` and was compiled from the source at `span`.
+#[derive(Clone, Copy, Debug)]
+struct SpanStart {
+    offset: usize,
+    span: Span,
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<u8>,
+    pub constants: Vec<Constant>,
+    spans: Vec<SpanStart>,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Chunk {
+            code: Vec::new(),
+            constants: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn write(&mut self, byte: u8, span: Span) {
+        let offset = self.code.len();
+        self.code.push(byte);
+
+        match self.spans.last() {
+            Some(last) if last.span == span => {},
+            _ => self.spans.push(SpanStart { offset, span }),
+        }
+    }
+
+    pub fn write_op(&mut self, op: OpCode, span: Span) {
+        self.write(op as u8, span);
+    }
+
+    pub fn write_u16(&mut self, value: u16, span: Span) {
+        self.write((value >> 8) as u8, span);
+        self.write((value & 0xff) as u8, span);
+    }
+
+    pub fn read_u16(&self, offset: usize) -> u16 {
+        ((self.code[offset] as u16) << 8) | self.code[offset + 1] as u16
+    }
+
+    pub fn add_constant(&mut self, constant: Constant) -> usize {
+        self.constants.push(constant);
+        self.constants.len() - 1
+    }
+
+    pub fn get_span(&self, offset: usize) -> Span {
+        let index = self.spans.partition_point(|start| start.offset <= offset);
+
+        match index {
+            0 => Span::default(),
+            _ => self.spans[index - 1].span,
+        }
+    }
+
+    pub fn get_line(&self, offset: usize) -> i32 {
+        self.get_span(offset).get_line()
+    }
+}
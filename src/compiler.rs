@@ -0,0 +1,846 @@
+use std::rc::Rc;
+
+use crate::chunk::{BytecodeFunction, Chunk, Constant, OpCode};
+use crate::error_hadling::{has_errors, Diagnostic, Phase};
+use crate::expr::*;
+use crate::stmt::Stmt;
+use crate::token::{LiteralPossibleValues, Span, Token};
+use crate::token_type::TokenType;
+
+struct Local {
+    name: String,
+    depth: usize,
+    is_captured: bool,
+}
+
+struct UpvalueRef {
+    index: u8,
+    is_local: bool,
+}
+
+// An enclosing loop: the scope depth its body starts above, and the forward jumps
+// emitted by `break` and `continue`, patched once their targets are known.
+struct LoopState {
+    scope_depth: usize,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+// What a function body belongs to, which decides what slot zero holds and what a
+// `return` without a value gives back.
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionKind {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+// Compilation state of one function body; nested declarations push a new one.
+struct FunctionState {
+    function: BytecodeFunction,
+    kind: FunctionKind,
+    locals: Vec<Local>,
+    upvalues: Vec<UpvalueRef>,
+    scope_depth: usize,
+    loops: Vec<LoopState>,
+}
+
+impl FunctionState {
+    fn new(name: String, kind: FunctionKind) -> Self {
+        // Slot zero holds the function being called, or the receiver of a method.
+        let callee = Local {
+            name: match kind {
+                FunctionKind::Method | FunctionKind::Initializer => "this".to_string(),
+                FunctionKind::Script | FunctionKind::Function => String::new(),
+            },
+            depth: 0,
+            is_captured: false,
+        };
+
+        FunctionState {
+            function: BytecodeFunction::new(name),
+            kind,
+            locals: vec![callee],
+            upvalues: Vec::new(),
+            scope_depth: 0,
+            loops: Vec::new(),
+        }
+    }
+}
+
+// Translates the parsed statements into bytecode for the `Vm`.
+pub struct Compiler {
+    states: Vec<FunctionState>,
+    // Source of the code being emitted, recorded in the chunk for runtime errors.
+    span: Span,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            states: Vec::new(),
+            span: Span::new(0, 0, 1, 1),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn compile(&mut self, statements: &[Stmt]) -> Option<Rc<BytecodeFunction>> {
+        self.states.push(FunctionState::new("script".to_string(), FunctionKind::Script));
+        self.diagnostics.clear();
+
+        for statement in statements {
+            self.statement(statement);
+        }
+
+        self.emit_op(OpCode::Nil);
+        self.emit_op(OpCode::Return);
+
+        let state = self.states.pop().unwrap();
+
+        if has_errors(&self.diagnostics) {
+            return None;
+        }
+
+        Some(Rc::new(state.function))
+    }
+
+    pub fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.clone()
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                for statement in statements {
+                    self.statement(statement);
+                }
+                self.end_scope();
+            },
+            Stmt::Break(keyword) | Stmt::Continue(keyword) => {
+                self.span = keyword.get_span();
+
+                let scope_depth = match self.current().loops.last() {
+                    Some(loop_state) => loop_state.scope_depth,
+                    None => return self.error(keyword.clone(), "Can't jump outside of a loop."),
+                };
+
+                self.discard_locals(scope_depth);
+                let jump = self.emit_jump(OpCode::Jump);
+
+                let loop_state = self.current_mut().loops.last_mut().unwrap();
+                if keyword.get_token_type() == TokenType::Break {
+                    loop_state.breaks.push(jump);
+                }
+                else {
+                    loop_state.continues.push(jump);
+                }
+            },
+            Stmt::Class(name, superclass, methods) => self.class(name, superclass, methods),
+            Stmt::Expr(expr) => {
+                self.expression(expr);
+                self.emit_op(OpCode::Pop);
+            },
+            Stmt::Function(name, params, body) => {
+                self.span = name.get_span();
+
+                if self.current().scope_depth > 0 {
+                    // Mark it initialized right away so the body can call itself.
+                    self.add_local(name);
+                    self.function(name, params, body, FunctionKind::Function);
+                }
+                else {
+                    self.function(name, params, body, FunctionKind::Function);
+                    self.define_global(name);
+                }
+            },
+            Stmt::If(_, condition, then_statement, else_statement) => {
+                self.expression(condition);
+
+                let then_jump = self.emit_jump(OpCode::JumpIfFalse);
+                self.emit_op(OpCode::Pop);
+                self.statement(then_statement);
+
+                let else_jump = self.emit_jump(OpCode::Jump);
+                self.patch_jump(then_jump);
+                self.emit_op(OpCode::Pop);
+
+                if let Some(else_stmt) = else_statement {
+                    self.statement(else_stmt);
+                }
+
+                self.patch_jump(else_jump);
+            },
+            Stmt::Print(_, expr) => {
+                self.expression(expr);
+                self.emit_op(OpCode::Print);
+            },
+            Stmt::Return(keyword, value) => {
+                self.span = keyword.get_span();
+
+                match value {
+                    Some(expr) => self.expression(expr),
+                    None => self.emit_return_value(),
+                }
+
+                self.emit_op(OpCode::Return);
+            },
+            Stmt::Var(_, name, initializer) => {
+                self.span = name.get_span();
+
+                match initializer {
+                    Some(expr) => self.expression(expr),
+                    None => self.emit_op(OpCode::Nil),
+                }
+
+                if self.current().scope_depth > 0 {
+                    self.add_local(name);
+                }
+                else {
+                    self.define_global(name);
+                }
+            },
+            Stmt::While(_, condition, body, increment) => {
+                let loop_start = self.chunk().code.len();
+
+                self.expression(condition);
+
+                let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
+                self.emit_op(OpCode::Pop);
+
+                let scope_depth = self.current().scope_depth;
+                self.current_mut().loops.push(LoopState { scope_depth, breaks: Vec::new(), continues: Vec::new() });
+                self.statement(body);
+                let loop_state = self.current_mut().loops.pop().unwrap();
+
+                for jump in loop_state.continues {
+                    self.patch_jump(jump);
+                }
+
+                if let Some(expr) = increment {
+                    self.expression(expr);
+                    self.emit_op(OpCode::Pop);
+                }
+
+                self.emit_loop(loop_start);
+
+                self.patch_jump(exit_jump);
+                self.emit_op(OpCode::Pop);
+
+                for jump in loop_state.breaks {
+                    self.patch_jump(jump);
+                }
+            },
+        }
+    }
+
+    // Leaves the class on the stack while its methods are attached, then stores it. With
+    // a superclass, a scope holding `super` wraps the methods so they can capture it.
+    fn class(&mut self, name: &Token, superclass: &Option<Expr>, methods: &[Stmt]) {
+        self.span = name.get_span();
+
+        let constant = self.identifier_constant(name);
+        self.emit_op(OpCode::Class);
+        self.emit_u16(constant);
+
+        if self.current().scope_depth > 0 {
+            self.add_local(name);
+        }
+        else {
+            self.define_global(name);
+        }
+
+        if let Some(superclass) = superclass {
+            self.expression(superclass);
+
+            self.begin_scope();
+            self.add_local(&Token::new(TokenType::Super, "super".to_string(), None, name.get_span()));
+
+            self.span = superclass.get_span();
+            self.variable(name, false);
+            self.emit_op(OpCode::Inherit);
+        }
+
+        self.span = name.get_span();
+        self.variable(name, false);
+
+        for method in methods {
+            if let Stmt::Function(method_name, params, body) = method {
+                let kind = if method_name.get_lexeme() == "init" { FunctionKind::Initializer } else { FunctionKind::Method };
+                self.function(method_name, params, body, kind);
+
+                let constant = self.identifier_constant(method_name);
+                self.emit_op(OpCode::Method);
+                self.emit_u16(constant);
+            }
+        }
+
+        self.emit_op(OpCode::Pop);
+
+        if superclass.is_some() {
+            self.end_scope();
+        }
+    }
+
+    fn function(&mut self, name: &Token, params: &[Token], body: &[Stmt], kind: FunctionKind) {
+        let mut state = FunctionState::new(name.get_lexeme(), kind);
+        state.function.arity = params.len();
+        state.scope_depth = 1;
+        self.states.push(state);
+
+        for param in params {
+            self.add_local(param);
+        }
+
+        for statement in body {
+            self.statement(statement);
+        }
+
+        self.emit_return_value();
+        self.emit_op(OpCode::Return);
+
+        let state = self.states.pop().unwrap();
+        let mut function = state.function;
+        function.upvalue_count = state.upvalues.len();
+
+        // The closure is created where the function is declared, not where its body ends.
+        self.span = name.get_span();
+
+        let constant = self.make_constant(Constant::Function(Rc::new(function)));
+        self.emit_op(OpCode::Closure);
+        self.emit_u16(constant);
+
+        for upvalue in state.upvalues {
+            self.emit_byte(upvalue.is_local as u8);
+            self.emit_byte(upvalue.index);
+        }
+    }
+
+    fn expression(&mut self, expression: &Expr) {
+        match expression {
+            Expr::Assign(Some(assign)) => {
+                self.expression(&assign.get_expression());
+                self.span = assign.get_value().get_span();
+                self.variable(&assign.get_value(), true);
+            },
+            Expr::Binary(Some(binary)) => {
+                self.expression(&binary.get_left());
+                self.expression(&binary.get_right());
+
+                let operator = binary.get_operator();
+                self.span = binary.get_span();
+
+                match operator.get_token_type() {
+                    TokenType::Plus => self.emit_op(OpCode::Add),
+                    TokenType::Minus => self.emit_op(OpCode::Subtract),
+                    TokenType::Star => self.emit_op(OpCode::Multiply),
+                    TokenType::Slash => self.emit_op(OpCode::Divide),
+                    TokenType::Percent => self.emit_op(OpCode::Modulo),
+                    TokenType::TildeSlash => self.emit_op(OpCode::FloorDivide),
+                    TokenType::StarStar => self.emit_op(OpCode::Power),
+                    TokenType::Greater => self.emit_op(OpCode::Greater),
+                    TokenType::GreaterEqual => self.emit_op(OpCode::GreaterEqual),
+                    TokenType::Less => self.emit_op(OpCode::Less),
+                    TokenType::LessEqual => self.emit_op(OpCode::LessEqual),
+                    TokenType::EqualEqual => self.emit_op(OpCode::Equal),
+                    TokenType::BangEqual => self.emit_op(OpCode::NotEqual),
+                    _ => self.error(operator, "Operator does not exist!"),
+                }
+            },
+            Expr::Call(Some(call)) => self.call(call),
+            Expr::Get(Some(get)) => {
+                self.expression(&get.get_object());
+
+                self.span = get.get_span();
+                let constant = self.identifier_constant(&get.get_name());
+                self.emit_op(OpCode::GetProperty);
+                self.emit_u16(constant);
+            },
+            Expr::Index(Some(index)) => {
+                self.expression(&index.get_object());
+                self.expression(&index.get_index());
+
+                self.span = index.get_span();
+                self.emit_op(OpCode::GetIndex);
+            },
+            Expr::List(Some(list)) => {
+                let elements = list.get_elements();
+                for element in &elements {
+                    self.expression(element);
+                }
+
+                if elements.len() > u16::MAX as usize {
+                    self.error_at_current_line("Too many elements in a list literal.");
+                    return;
+                }
+
+                self.emit_op(OpCode::BuildList);
+                self.emit_u16(elements.len() as u16);
+            },
+            Expr::Grouping(Some(grouping)) => self.expression(&grouping.get_expression()),
+            Expr::Literal(Some(literal)) => {
+                let token = literal.get_value();
+                self.span = token.get_span();
+
+                match (token.get_token_type(), token.get_literal()) {
+                    (TokenType::True, _) => self.emit_op(OpCode::True),
+                    (TokenType::False, _) => self.emit_op(OpCode::False),
+                    (TokenType::Nil, _) => self.emit_op(OpCode::Nil),
+                    (_, Some(LiteralPossibleValues::DoubleValue(number))) => self.emit_constant(Constant::Number(number)),
+                    (_, Some(LiteralPossibleValues::StringValue(string))) => self.emit_constant(Constant::String(string)),
+                    _ => self.error(token, "The Token is not a literal."),
+                }
+            },
+            Expr::Logical(Some(logical)) => {
+                self.expression(&logical.get_left());
+                self.span = logical.get_operator().get_span();
+
+                if logical.get_operator().get_token_type() == TokenType::Or {
+                    let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+                    let end_jump = self.emit_jump(OpCode::Jump);
+
+                    self.patch_jump(else_jump);
+                    self.emit_op(OpCode::Pop);
+                    self.expression(&logical.get_right());
+                    self.patch_jump(end_jump);
+                }
+                else {
+                    let end_jump = self.emit_jump(OpCode::JumpIfFalse);
+
+                    self.emit_op(OpCode::Pop);
+                    self.expression(&logical.get_right());
+                    self.patch_jump(end_jump);
+                }
+            },
+            Expr::Map(Some(map)) => {
+                let entries = map.get_entries();
+                for (key, value) in &entries {
+                    self.expression(key);
+                    self.expression(value);
+                }
+
+                if entries.len() > u16::MAX as usize {
+                    self.error_at_current_line("Too many entries in a map literal.");
+                    return;
+                }
+
+                self.span = map.get_span();
+                self.emit_op(OpCode::BuildMap);
+                self.emit_u16(entries.len() as u16);
+            },
+            Expr::Set(Some(set)) => {
+                self.expression(&set.get_object());
+                self.expression(&set.get_value());
+
+                self.span = set.get_span();
+                let constant = self.identifier_constant(&set.get_name());
+                self.emit_op(OpCode::SetProperty);
+                self.emit_u16(constant);
+            },
+            Expr::SetIndex(Some(set_index)) => {
+                self.expression(&set_index.get_object());
+                self.expression(&set_index.get_index());
+                self.expression(&set_index.get_value());
+
+                self.span = set_index.get_span();
+                self.emit_op(OpCode::SetIndex);
+            },
+            Expr::Super(Some(super_expr)) => {
+                self.span = super_expr.get_span();
+                self.variable(&this_token(&super_expr.get_keyword()), false);
+                self.variable(&super_expr.get_keyword(), false);
+
+                let constant = self.identifier_constant(&super_expr.get_method());
+                self.emit_op(OpCode::GetSuper);
+                self.emit_u16(constant);
+            },
+            Expr::This(Some(this)) => {
+                self.span = this.get_span();
+                self.variable(&this.get_keyword(), false);
+            },
+            Expr::Unary(Some(unary)) => {
+                self.expression(&unary.get_expression());
+                self.span = unary.get_span();
+
+                match unary.get_operator().get_token_type() {
+                    TokenType::Minus => self.emit_op(OpCode::Negate),
+                    TokenType::Bang => self.emit_op(OpCode::Not),
+                    _ => self.error(unary.get_operator(), "The token is unary, but do not have an unary operator!"),
+                }
+            },
+            Expr::Update(Some(update)) => self.update(update),
+            Expr::Variable(Some(variable)) => {
+                self.span = variable.get_value().get_span();
+                self.variable(&variable.get_value(), false);
+            },
+            _ => {},
+        }
+    }
+
+    // `target op= value`, `++target` or `target++`, where the target is a variable, a
+    // field or an index. The object and index are evaluated once and duplicated for the
+    // read. A postfix update keeps the old value as its result: a variable is simply
+    // loaded twice, while for a field or an index a placeholder pushed first is
+    // overwritten with the old value, which stays once the store has been popped.
+    fn update(&mut self, update: &Update) {
+        let postfix = update.is_postfix();
+
+        match *update.get_target() {
+            Expr::Variable(Some(variable)) => {
+                let name = variable.get_value();
+
+                self.span = update.get_span();
+                self.variable(&name, false);
+                if postfix {
+                    self.variable(&name, false);
+                }
+
+                self.update_operator(update);
+                self.variable(&name, true);
+            },
+            Expr::Get(Some(get)) => {
+                self.span = update.get_span();
+                if postfix {
+                    self.emit_op(OpCode::Nil);
+                }
+
+                self.expression(&get.get_object());
+
+                self.span = get.get_span();
+                let constant = self.identifier_constant(&get.get_name());
+                self.emit_op(OpCode::Duplicate);
+                self.emit_byte(1);
+                self.emit_op(OpCode::GetProperty);
+                self.emit_u16(constant);
+                if postfix {
+                    self.emit_op(OpCode::SetBelow);
+                    self.emit_byte(2);
+                }
+
+                self.update_operator(update);
+                self.emit_op(OpCode::SetProperty);
+                self.emit_u16(constant);
+            },
+            Expr::Index(Some(index)) => {
+                self.span = update.get_span();
+                if postfix {
+                    self.emit_op(OpCode::Nil);
+                }
+
+                self.expression(&index.get_object());
+                self.expression(&index.get_index());
+
+                self.span = index.get_span();
+                self.emit_op(OpCode::Duplicate);
+                self.emit_byte(2);
+                self.emit_op(OpCode::GetIndex);
+                if postfix {
+                    self.emit_op(OpCode::SetBelow);
+                    self.emit_byte(3);
+                }
+
+                self.update_operator(update);
+                self.emit_op(OpCode::SetIndex);
+            },
+            _ => return self.error(update.get_operator(), "Invalid assignment target."),
+        }
+
+        if postfix {
+            self.emit_op(OpCode::Pop);
+        }
+    }
+
+    // Combines the current value of an update's target, on top of the stack, with its value.
+    fn update_operator(&mut self, update: &Update) {
+        self.expression(&update.get_value());
+
+        let operator = update.get_operator();
+        self.span = update.get_span();
+
+        match operator.get_token_type() {
+            TokenType::PlusEqual | TokenType::PlusPlus => self.emit_op(OpCode::Add),
+            TokenType::MinusEqual | TokenType::MinusMinus => self.emit_op(OpCode::Subtract),
+            TokenType::StarEqual => self.emit_op(OpCode::Multiply),
+            TokenType::SlashEqual => self.emit_op(OpCode::Divide),
+            _ => self.error(operator, "The token is not an assignment operator."),
+        }
+    }
+
+    // A call of a method on an instance or on `super` is invoked directly, without first
+    // creating the bound method. `super` itself is loaded after the arguments.
+    fn call(&mut self, call: &Call) {
+        let arguments = call.get_arguments();
+
+        let (invoke, superclass) = match *call.get_callee() {
+            Expr::Get(Some(get)) => {
+                self.expression(&get.get_object());
+                (Some((OpCode::Invoke, get.get_name())), None)
+            },
+            Expr::Super(Some(super_expr)) => {
+                self.span = super_expr.get_span();
+                self.variable(&this_token(&super_expr.get_keyword()), false);
+                (Some((OpCode::SuperInvoke, super_expr.get_method())), Some(super_expr.get_keyword()))
+            },
+            callee => {
+                self.expression(&callee);
+                (None, None)
+            },
+        };
+
+        for argument in &arguments {
+            self.expression(argument);
+        }
+
+        self.span = call.get_span();
+        if let Some(keyword) = superclass {
+            self.variable(&keyword, false);
+        }
+
+        match invoke {
+            Some((op, name)) => {
+                let constant = self.identifier_constant(&name);
+                self.emit_op(op);
+                self.emit_u16(constant);
+            },
+            None => self.emit_op(OpCode::Call),
+        }
+
+        self.emit_byte(arguments.len() as u8);
+    }
+
+    // Emits the load or store for `name`, looking at locals, then enclosing functions, then globals.
+    fn variable(&mut self, name: &Token, assign: bool) {
+        let state = self.states.len() - 1;
+
+        if let Some(slot) = self.resolve_local(state, name) {
+            self.emit_op(if assign { OpCode::SetLocal } else { OpCode::GetLocal });
+            self.emit_byte(slot);
+        }
+        else if let Some(index) = self.resolve_upvalue(state, name) {
+            self.emit_op(if assign { OpCode::SetUpvalue } else { OpCode::GetUpvalue });
+            self.emit_byte(index);
+        }
+        else {
+            let constant = self.identifier_constant(name);
+            self.emit_op(if assign { OpCode::SetGlobal } else { OpCode::GetGlobal });
+            self.emit_u16(constant);
+        }
+    }
+
+    fn resolve_local(&self, state: usize, name: &Token) -> Option<u8> {
+        let locals = &self.states[state].locals;
+
+        locals.iter().rposition(|local| local.name == name.get_lexeme()).map(|slot| slot as u8)
+    }
+
+    fn resolve_upvalue(&mut self, state: usize, name: &Token) -> Option<u8> {
+        if state == 0 {
+            return None;
+        }
+
+        if let Some(slot) = self.resolve_local(state - 1, name) {
+            self.states[state - 1].locals[slot as usize].is_captured = true;
+            return Some(self.add_upvalue(state, slot, true, name));
+        }
+
+        if let Some(index) = self.resolve_upvalue(state - 1, name) {
+            return Some(self.add_upvalue(state, index, false, name));
+        }
+
+        None
+    }
+
+    fn add_upvalue(&mut self, state: usize, index: u8, is_local: bool, name: &Token) -> u8 {
+        let upvalues = &self.states[state].upvalues;
+
+        if let Some(existing) = upvalues.iter().position(|upvalue| upvalue.index == index && upvalue.is_local == is_local) {
+            return existing as u8;
+        }
+
+        if upvalues.len() == 256 {
+            self.error(name.clone(), "Too many closure variables in function.");
+            return 0;
+        }
+
+        self.states[state].upvalues.push(UpvalueRef { index, is_local });
+        (self.states[state].upvalues.len() - 1) as u8
+    }
+
+    fn add_local(&mut self, name: &Token) {
+        if self.current().locals.len() == 256 {
+            self.error(name.clone(), "Too many local variables in function.");
+            return;
+        }
+
+        let depth = self.current().scope_depth;
+        self.current_mut().locals.push(Local {
+            name: name.get_lexeme(),
+            depth,
+            is_captured: false,
+        });
+    }
+
+    fn define_global(&mut self, name: &Token) {
+        let constant = self.identifier_constant(name);
+        self.emit_op(OpCode::DefineGlobal);
+        self.emit_u16(constant);
+    }
+
+    fn begin_scope(&mut self) {
+        self.current_mut().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        self.current_mut().scope_depth -= 1;
+
+        loop {
+            let state = self.current();
+            let captured = match state.locals.last() {
+                Some(local) if local.depth > state.scope_depth => local.is_captured,
+                _ => break,
+            };
+
+            self.emit_op(if captured { OpCode::CloseUpvalue } else { OpCode::Pop });
+            self.current_mut().locals.pop();
+        }
+    }
+
+    // Pops the locals declared deeper than `depth` without forgetting them, for a
+    // jump that leaves their scopes while the compiler is still inside them.
+    fn discard_locals(&mut self, depth: usize) {
+        let captured: Vec<bool> = self.current().locals.iter().rev()
+            .take_while(|local| local.depth > depth)
+            .map(|local| local.is_captured)
+            .collect();
+
+        for is_captured in captured {
+            self.emit_op(if is_captured { OpCode::CloseUpvalue } else { OpCode::Pop });
+        }
+    }
+
+    fn identifier_constant(&mut self, name: &Token) -> u16 {
+        let lexeme = name.get_lexeme();
+
+        let existing = self.chunk().constants.iter().position(|constant| {
+            matches!(constant, Constant::String(value) if *value == lexeme)
+        });
+
+        match existing {
+            Some(index) => index as u16,
+            None => self.make_constant(Constant::String(lexeme)),
+        }
+    }
+
+    fn make_constant(&mut self, constant: Constant) -> u16 {
+        let index = self.chunk_mut().add_constant(constant);
+
+        if index > u16::MAX as usize {
+            self.error_at_current_line("Too many constants in one chunk.");
+            return 0;
+        }
+
+        index as u16
+    }
+
+    fn emit_constant(&mut self, constant: Constant) {
+        let index = self.make_constant(constant);
+        self.emit_op(OpCode::Constant);
+        self.emit_u16(index);
+    }
+
+    fn emit_jump(&mut self, op: OpCode) -> usize {
+        self.emit_op(op);
+        self.emit_u16(u16::MAX);
+
+        self.chunk().code.len() - 2
+    }
+
+    fn patch_jump(&mut self, offset: usize) {
+        let jump = self.chunk().code.len() - offset - 2;
+
+        if jump > u16::MAX as usize {
+            self.error_at_current_line("Too much code to jump over.");
+            return;
+        }
+
+        let chunk = self.chunk_mut();
+        chunk.code[offset] = (jump >> 8) as u8;
+        chunk.code[offset + 1] = (jump & 0xff) as u8;
+    }
+
+    fn emit_loop(&mut self, loop_start: usize) {
+        self.emit_op(OpCode::Loop);
+
+        let offset = self.chunk().code.len() - loop_start + 2;
+
+        if offset > u16::MAX as usize {
+            self.error_at_current_line("Loop body too large.");
+        }
+
+        self.emit_u16(offset as u16);
+    }
+
+    // What a function gives back when its body ends or it returns without a value: the
+    // instance for an initializer, nil otherwise.
+    fn emit_return_value(&mut self) {
+        if self.current().kind == FunctionKind::Initializer {
+            self.emit_op(OpCode::GetLocal);
+            self.emit_byte(0);
+        }
+        else {
+            self.emit_op(OpCode::Nil);
+        }
+    }
+
+    fn emit_op(&mut self, op: OpCode) {
+        let span = self.span;
+        self.chunk_mut().write_op(op, span);
+    }
+
+    fn emit_byte(&mut self, byte: u8) {
+        let span = self.span;
+        self.chunk_mut().write(byte, span);
+    }
+
+    fn emit_u16(&mut self, value: u16) {
+        let span = self.span;
+        self.chunk_mut().write_u16(value, span);
+    }
+
+    fn current(&self) -> &FunctionState {
+        self.states.last().unwrap()
+    }
+
+    fn current_mut(&mut self) -> &mut FunctionState {
+        self.states.last_mut().unwrap()
+    }
+
+    fn chunk(&self) -> &Chunk {
+        &self.current().function.chunk
+    }
+
+    fn chunk_mut(&mut self) -> &mut Chunk {
+        &mut self.current_mut().function.chunk
+    }
+
+    fn error(&mut self, token: Token, message: &str) {
+        self.diagnostics.push(Diagnostic::error_at(Phase::Compile, &token, message.to_string()));
+    }
+
+    fn error_at_current_line(&mut self, message: &str) {
+        self.diagnostics.push(Diagnostic::error(Phase::Compile, self.span.get_line(), message.to_string()));
+    }
+}
+
+// The `this` that a `super` expression at `keyword` is called on.
+fn this_token(keyword: &Token) -> Token {
+    Token::new(TokenType::This, "this".to_string(), None, keyword.get_span())
+}
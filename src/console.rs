@@ -0,0 +1,53 @@
+use std::io::{self, BufRead, Read, Write};
+
+use crate::native::{native, string_argument};
+use crate::value::Value;
+
+// Reads one line from stdin without its line ending, or `None` at end of input.
+// The prompt and the script's input() both go through here, so they consume
+// the same buffered stdin one line at a time.
+pub fn read_line() -> io::Result<Option<String>> {
+    let mut buffer = String::new();
+
+    if io::stdin().lock().read_line(&mut buffer)? == 0 {
+        return Ok(None);
+    }
+
+    let length = buffer.trim_end_matches(['\n', '\r']).len();
+    buffer.truncate(length);
+
+    Ok(Some(buffer))
+}
+
+// Functions reading from stdin, defined as globals.
+pub fn library() -> Vec<(String, Value)> {
+    vec![
+        native("readLine", 0, |_arguments| line_value()),
+        native("input", 1, |arguments| {
+            let prompt = string_argument("input", &arguments, 0)?;
+
+            print!("{}", prompt);
+            io::stdout().flush().map_err(|e| e.to_string())?;
+
+            line_value()
+        }),
+        native("readAll", 0, |_arguments| {
+            let mut buffer = String::new();
+            io::stdin().lock().read_to_string(&mut buffer).map_err(|e| format!("Could not read stdin: {}", e))?;
+
+            if buffer.is_empty() {
+                return Ok(Value::Nil);
+            }
+
+            Ok(Value::string(buffer))
+        }),
+    ]
+}
+
+fn line_value() -> Result<Value, String> {
+    match read_line() {
+        Ok(Some(line)) => Ok(Value::string(line)),
+        Ok(None) => Ok(Value::Nil),
+        Err(e) => Err(format!("Could not read stdin: {}", e)),
+    }
+}
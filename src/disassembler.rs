@@ -0,0 +1,121 @@
+use std::fmt::Write;
+
+use crate::chunk::{Chunk, Constant, OpCode};
+
+// Renders a chunk, and every function compiled into its constant pool, as one instruction per line:
+// byte offset, source line (`|` when unchanged from the previous instruction), opcode and operands.
+pub fn disassemble_chunk(chunk: &Chunk, name: &str) -> String {
+    let mut output = format!("== {} ==\n", name);
+
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        offset = disassemble_instruction(chunk, offset, &mut output);
+    }
+
+    for constant in &chunk.constants {
+        if let Constant::Function(function) = constant {
+            output.push('\n');
+            output.push_str(&disassemble_chunk(&function.chunk, &format!("<fn {}>", function.name)));
+        }
+    }
+
+    output
+}
+
+// Appends the instruction at `offset` to `output` and returns the offset of the next one.
+pub fn disassemble_instruction(chunk: &Chunk, offset: usize, output: &mut String) -> usize {
+    let _ = write!(output, "{:04} ", offset);
+
+    let line = chunk.get_line(offset);
+    if offset > 0 && line == chunk.get_line(offset - 1) {
+        output.push_str("   | ");
+    }
+    else {
+        let _ = write!(output, "{:4} ", line);
+    }
+
+    let op = match OpCode::from_byte(chunk.code[offset]) {
+        Some(op) => op,
+        None => {
+            let _ = writeln!(output, "Unknown opcode {}", chunk.code[offset]);
+            return offset + 1;
+        },
+    };
+
+    match op {
+        OpCode::Constant | OpCode::GetGlobal | OpCode::DefineGlobal | OpCode::SetGlobal
+        | OpCode::Class | OpCode::Method | OpCode::GetProperty | OpCode::SetProperty | OpCode::GetSuper => {
+            constant_instruction(op, chunk, offset, output)
+        },
+        OpCode::Invoke | OpCode::SuperInvoke => invoke_instruction(op, chunk, offset, output),
+        OpCode::Duplicate | OpCode::SetBelow
+        | OpCode::GetLocal | OpCode::SetLocal | OpCode::GetUpvalue | OpCode::SetUpvalue | OpCode::Call => {
+            byte_instruction(op, chunk, offset, output)
+        },
+        OpCode::BuildList | OpCode::BuildMap => count_instruction(op, chunk, offset, output),
+        OpCode::Jump | OpCode::JumpIfFalse => jump_instruction(op, 1, chunk, offset, output),
+        OpCode::Loop => jump_instruction(op, -1, chunk, offset, output),
+        OpCode::Closure => closure_instruction(chunk, offset, output),
+        _ => {
+            let _ = writeln!(output, "{:?}", op);
+            offset + 1
+        },
+    }
+}
+
+fn constant_instruction(op: OpCode, chunk: &Chunk, offset: usize, output: &mut String) -> usize {
+    let index = chunk.read_u16(offset + 1) as usize;
+    let _ = writeln!(output, "{:<16} {:4} '{}'", format!("{:?}", op), index, chunk.constants[index]);
+
+    offset + 3
+}
+
+fn invoke_instruction(op: OpCode, chunk: &Chunk, offset: usize, output: &mut String) -> usize {
+    let index = chunk.read_u16(offset + 1) as usize;
+    let argument_count = chunk.code[offset + 3];
+    let _ = writeln!(output, "{:<16} {:4} '{}' ({} args)", format!("{:?}", op), index, chunk.constants[index], argument_count);
+
+    offset + 4
+}
+
+fn byte_instruction(op: OpCode, chunk: &Chunk, offset: usize, output: &mut String) -> usize {
+    let operand = chunk.code[offset + 1];
+    let _ = writeln!(output, "{:<16} {:4}", format!("{:?}", op), operand);
+
+    offset + 2
+}
+
+fn count_instruction(op: OpCode, chunk: &Chunk, offset: usize, output: &mut String) -> usize {
+    let count = chunk.read_u16(offset + 1);
+    let _ = writeln!(output, "{:<16} {:4}", format!("{:?}", op), count);
+
+    offset + 3
+}
+
+fn jump_instruction(op: OpCode, sign: i64, chunk: &Chunk, offset: usize, output: &mut String) -> usize {
+    let jump = chunk.read_u16(offset + 1) as i64;
+    let target = offset as i64 + 3 + sign * jump;
+    let _ = writeln!(output, "{:<16} {:4} -> {}", format!("{:?}", op), offset, target);
+
+    offset + 3
+}
+
+fn closure_instruction(chunk: &Chunk, offset: usize, output: &mut String) -> usize {
+    let index = chunk.read_u16(offset + 1) as usize;
+    let constant = &chunk.constants[index];
+    let _ = writeln!(output, "{:<16} {:4} {}", "Closure", index, constant);
+
+    let mut offset = offset + 3;
+
+    if let Constant::Function(function) = constant {
+        for _ in 0..function.upvalue_count {
+            let is_local = chunk.code[offset] == 1;
+            let slot = chunk.code[offset + 1];
+            let kind = if is_local { "local" } else { "upvalue" };
+            let _ = writeln!(output, "{:04}    |                     {} {}", offset, kind, slot);
+            offset += 2;
+        }
+    }
+
+    offset
+}
@@ -2,13 +2,19 @@ use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
 
-use crate::expr::Expr;
+use crate::value::Value;
 use crate::token::Token;
 
 #[derive(Clone, Debug)]
 pub struct Environment {
     pub enclosing: Option<Rc<RefCell<Environment>>>,
-    pub values: HashMap<String, Option<Expr>>,
+    pub values: HashMap<String, Value>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Environment {
@@ -34,11 +40,22 @@ impl Environment {
         self.enclosing.clone()
     }
 
-    pub fn define(&mut self, name: String, value: Option<Expr>) {
+    // Every name visible from this scope, innermost first.
+    pub fn get_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.values.keys().cloned().collect();
+
+        if let Some(ref enclosing) = self.enclosing {
+            names.extend(enclosing.borrow().get_names());
+        }
+
+        names
+    }
+
+    pub fn define(&mut self, name: String, value: Value) {
         self.values.insert(name, value);
     }
 
-    pub fn get(&self, name: Token) -> Result<Option<Expr>, String> {
+    pub fn get(&self, name: Token) -> Result<Value, String> {
         if let Some(value) = self.values.get(&name.get_lexeme()) {
             return Ok(value.clone());
         }
@@ -47,12 +64,43 @@ impl Environment {
             return enclosing.borrow().get(name);
         }
 
-        Err(format!("[ERROR] {} is not defined!", name.get_lexeme()))
+        Err(format!("{} is not defined!", name.get_lexeme()))
+    }
+
+    pub fn get_at(&self, distance: usize, name: Token) -> Result<Value, String> {
+        if distance == 0 {
+            return match self.values.get(&name.get_lexeme()) {
+                Some(value) => Ok(value.clone()),
+                None => Err(format!("{} is not defined!", name.get_lexeme())),
+            };
+        }
+
+        match self.enclosing {
+            Some(ref enclosing) => enclosing.borrow().get_at(distance - 1, name),
+            None => Err(format!("{} is not defined!", name.get_lexeme())),
+        }
+    }
+
+    pub fn assign_at(&mut self, distance: usize, name: String, data: Value) -> Result<(), String> {
+        if distance == 0 {
+            return match self.values.get_mut(&name) {
+                Some(value) => {
+                    *value = data;
+                    Ok(())
+                },
+                None => Err(format!("{} is not defined!", name)),
+            };
+        }
+
+        match self.enclosing {
+            Some(ref enclosing) => enclosing.borrow_mut().assign_at(distance - 1, name, data),
+            None => Err(format!("{} is not defined!", name)),
+        }
     }
 
-    pub fn assign(&mut self, name: String, data: Option<Expr>) -> Result<(), String> {
-        if self.values.contains_key(&name) {
-            self.values.insert(name, data);
+    pub fn assign(&mut self, name: String, data: Value) -> Result<(), String> {
+        if let Some(value) = self.values.get_mut(&name) {
+            *value = data;
             return Ok(());
         }
 
@@ -60,6 +108,6 @@ impl Environment {
             return enclosing.borrow_mut().assign(name, data);
         }
 
-        Err(format!("[ERROR] {} is not defined!", name))
+        Err(format!("{} is not defined!", name))
     }
 }
@@ -1,31 +1,369 @@
-use crate::{token::Token, token_type::TokenType};
+use std::fmt;
 
-pub static mut HAD_ERROR: bool = false;
-pub static mut HAD_RUNTIME_ERROR: bool = false;
+use crate::{token::{Span, Token}, token_type::TokenType};
 
-pub fn error(line: i32, message: &str) {
-    report(line, "", message);
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
 }
 
-pub fn runtime_error(token: Token, message: String) {
-    println!("{} \n[line {}]", message, token.get_line());
-    unsafe {
-        HAD_RUNTIME_ERROR = true;
+// The stage of the pipeline that produced a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    Scan,
+    Parse,
+    Resolve,
+    Compile,
+    Runtime,
+}
+
+// A function that was running when a runtime error happened, and the line it was called from.
+#[derive(Clone, Debug)]
+pub struct StackFrame {
+    function: String,
+    line: i32,
+}
+
+impl StackFrame {
+    pub fn new(function: String, line: i32) -> Self {
+        StackFrame {
+            function,
+            line,
+        }
+    }
+
+    pub fn get_function(&self) -> String {
+        self.function.clone()
+    }
+
+    pub fn get_line(&self) -> i32 {
+        self.line
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    line: i32,
+    span: Option<Span>,
+    // Text of the offending token, empty when the error is at the end of the input.
+    lexeme: Option<String>,
+    phase: Phase,
+    // Extra hints shown under the snippet, such as a likely misspelling.
+    notes: Vec<String>,
+    // Active calls at the moment of a runtime error, innermost first.
+    trace: Vec<StackFrame>,
+}
+
+impl Diagnostic {
+    pub fn error(phase: Phase, line: i32, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message,
+            line,
+            span: None,
+            lexeme: None,
+            phase,
+            notes: Vec::new(),
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn error_at(phase: Phase, token: &Token, message: String) -> Self {
+        let lexeme = match token.get_token_type() {
+            TokenType::Eof => String::new(),
+            _ => token.get_lexeme(),
+        };
+
+        Diagnostic {
+            lexeme: Some(lexeme),
+            ..Diagnostic::error(phase, token.get_line(), message).with_span(token.get_span())
+        }
+    }
+
+    // Points the diagnostic at `span`, which also moves it to the line the span starts on.
+    pub fn with_span(self, span: Span) -> Self {
+        let line = if span == Span::default() { self.line } else { span.get_line() };
+
+        Diagnostic {
+            line,
+            span: Some(span),
+            ..self
+        }
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_trace(self, trace: Vec<StackFrame>) -> Self {
+        Diagnostic {
+            trace,
+            ..self
+        }
+    }
+
+    pub fn get_severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn get_line(&self) -> i32 {
+        self.line
+    }
+
+    pub fn get_column(&self) -> Option<usize> {
+        self.span.map(|span| span.get_column())
+    }
+
+    pub fn get_span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn get_lexeme(&self) -> Option<String> {
+        self.lexeme.clone()
+    }
+
+    pub fn get_phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn get_notes(&self) -> Vec<String> {
+        self.notes.clone()
+    }
+
+    pub fn get_trace(&self) -> Vec<StackFrame> {
+        self.trace.clone()
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location = match self.get_column() {
+            Some(column) => format!("line {}, column {}", self.line, column),
+            None => format!("line {}", self.line),
+        };
+
+        if self.phase == Phase::Runtime {
+            return write!(f, "{} \n[{}]", self.message, location);
+        }
+
+        let where_err = match &self.lexeme {
+            None => String::new(),
+            Some(lexeme) if lexeme.is_empty() => " at end".to_string(),
+            Some(lexeme) => format!(" at '{}'", lexeme),
+        };
+
+        write!(f, "[{}] Error {} : {}", location, where_err, self.message)
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|diagnostic| diagnostic.is_error())
+}
+
+// The candidate closest to `name`, if any is close enough to be a plausible typo.
+pub fn suggest_name(name: &str, candidates: &[String]) -> Option<String> {
+    // A distance as large as the name itself would match any short candidate.
+    let length = name.chars().count();
+    let max_distance = (length / 3).max(1).min(length.saturating_sub(1));
+
+    candidates.iter()
+        .filter(|candidate| candidate.as_str() != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.clone())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
     }
+
+    previous[b.len()]
 }
 
-pub fn report(line: i32, where_err: &str, message: &str) {
-    println!("[line {}] Error {} : {}", line, where_err, message);
-    unsafe {
-        HAD_ERROR = true;
+impl Severity {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
     }
 }
 
-pub fn parser_error(token: Token, message: String) {
-    if token.get_token_type() == TokenType::Eof {
-        report(token.get_line(), " at end", message.as_str());
+impl Phase {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Phase::Scan => "scan",
+            Phase::Parse => "parse",
+            Phase::Resolve => "resolve",
+            Phase::Compile => "compile",
+            Phase::Runtime => "runtime",
+        }
     }
-    else {
-        report(token.get_line(), (" at '".to_string()+ token.get_lexeme().as_str() + "'").as_str(), message.as_str());
+}
+
+// Renders a diagnostic as a single-line JSON object, for editors and CI tools.
+pub fn render_json(diagnostic: &Diagnostic, file_name: &str) -> String {
+    let column = match diagnostic.get_column() {
+        Some(column) => column.to_string(),
+        None => "null".to_string(),
+    };
+
+    let lexeme = match &diagnostic.lexeme {
+        Some(lexeme) => json_string(lexeme),
+        None => "null".to_string(),
+    };
+
+    let notes: Vec<String> = diagnostic.notes.iter().map(|note| json_string(note)).collect();
+
+    let trace: Vec<String> = diagnostic.trace.iter()
+        .map(|frame| format!("{{\"function\":{},\"line\":{}}}", json_string(&frame.function), frame.line))
+        .collect();
+
+    format!(
+        "{{\"file\":{},\"line\":{},\"column\":{},\"phase\":\"{}\",\"severity\":\"{}\",\"message\":{},\"lexeme\":{},\"notes\":[{}],\"trace\":[{}]}}",
+        json_string(file_name),
+        diagnostic.line,
+        column,
+        diagnostic.phase.get_name(),
+        diagnostic.severity.get_name(),
+        json_string(&diagnostic.message),
+        lexeme,
+        notes.join(","),
+        trace.join(","),
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut output = String::from("\"");
+
+    for character in text.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
     }
-}
\ No newline at end of file
+
+    output.push('"');
+    output
+}
+
+// The most stack trace entries `render` lists before summarising the rest.
+const TRACE_LIMIT: usize = 20;
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+// Renders a diagnostic the way rustc does: the message, a `file:line:column`
+// pointer, the offending source line with the span underlined, then any notes.
+pub fn render(diagnostic: &Diagnostic, file_name: &str, source: &str, colour: bool) -> String {
+    let paint = |style: &str, text: &str| -> String {
+        if colour {
+            format!("{}{}{}", style, text, RESET)
+        }
+        else {
+            text.to_string()
+        }
+    };
+
+    let label = diagnostic.severity.get_name();
+    let label_style = match diagnostic.severity {
+        Severity::Error => RED,
+        Severity::Warning => YELLOW,
+    };
+
+    let line = diagnostic.line;
+    let gutter = " ".repeat(line.to_string().len());
+
+    let mut output = format!("{}{}\n", paint(label_style, label), paint(BOLD, &format!(": {}", diagnostic.message)));
+
+    let location = match diagnostic.get_column() {
+        Some(column) => format!("{}:{}:{}", file_name, line, column),
+        None => format!("{}:{}", file_name, line),
+    };
+    output.push_str(&format!("{}{} {}\n", gutter, paint(BLUE, "-->"), location));
+
+    if let Some(source_line) = source.lines().nth((line - 1).max(0) as usize) {
+        let bar = paint(BLUE, "|");
+        output.push_str(&format!("{} {}\n", gutter, bar));
+        output.push_str(&format!("{} {} {}\n", paint(BLUE, &line.to_string()), bar, source_line));
+
+        if let Some(span) = diagnostic.span {
+            let column = span.get_column().max(1);
+            let line_length = source_line.chars().count();
+            let available = (line_length + 1).saturating_sub(column - 1).max(1);
+            let spanned = source.get(span.get_start()..span.get_end()).unwrap_or_default();
+            let width = spanned.chars().count().clamp(1, available);
+
+            let underline = format!("{}{}", " ".repeat(column - 1), paint(label_style, &"^".repeat(width)));
+            output.push_str(&format!("{} {} {}\n", gutter, bar, underline));
+        }
+    }
+
+    for note in &diagnostic.notes {
+        output.push_str(&format!("{} {} {}\n", gutter, paint(BLUE, "="), paint(BOLD, &format!("note: {}", note))));
+    }
+
+    if !diagnostic.trace.is_empty() {
+        output.push_str(&format!("{} {} {}\n", gutter, paint(BLUE, "="), paint(BOLD, "call stack (innermost first):")));
+
+        // A run of identical frames, as deep recursion leaves behind, is listed once with
+        // a count, and only the innermost TRACE_LIMIT entries are shown.
+        let trace = &diagnostic.trace;
+        let mut depth = 0;
+        let mut shown = 0;
+
+        while depth < trace.len() {
+            if shown == TRACE_LIMIT {
+                output.push_str(&format!("{}     ... {} more frames\n", gutter, trace.len() - depth));
+                break;
+            }
+
+            let frame = &trace[depth];
+            let run = trace[depth..].iter()
+                .take_while(|other| other.function == frame.function && other.line == frame.line)
+                .count();
+
+            output.push_str(&format!("{}     {}: {}, called at line {}\n", gutter, depth, frame.function, frame.line));
+            if run > 1 {
+                output.push_str(&format!("{}     [previous frame repeated {} more times]\n", gutter, run - 1));
+            }
+
+            depth += run;
+            shown += 1;
+        }
+    }
+
+    output
+}
@@ -1,4 +1,14 @@
-use crate::token::Token;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::token::{Span, Token};
+
+// Expressions are cloned freely, so the resolver identifies variable
+// references by an id handed out when the node is created.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
 
 #[derive(Clone, Debug)]
 pub enum Expr {
@@ -8,11 +18,48 @@ pub enum Expr {
     Unary(Option<Unary>),
     Binary(Option<Binary>),
     Variable(Option<Variable>),
-    Assign(Option<Assign>)
+    Assign(Option<Assign>),
+    Call(Option<Call>),
+    Get(Option<Get>),
+    Set(Option<Set>),
+    This(Option<This>),
+    Super(Option<Super>),
+    List(Option<List>),
+    Map(Option<Map>),
+    Index(Option<Index>),
+    SetIndex(Option<SetIndex>),
+    Update(Option<Update>)
+}
+
+impl Expr {
+    // The source range the expression was parsed from.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Expr::Literal(Some(literal)) => literal.get_span(),
+            Expr::Grouping(Some(grouping)) => grouping.get_span(),
+            Expr::Logical(Some(logical)) => logical.get_span(),
+            Expr::Unary(Some(unary)) => unary.get_span(),
+            Expr::Binary(Some(binary)) => binary.get_span(),
+            Expr::Variable(Some(variable)) => variable.get_span(),
+            Expr::Assign(Some(assign)) => assign.get_span(),
+            Expr::Call(Some(call)) => call.get_span(),
+            Expr::Get(Some(get)) => get.get_span(),
+            Expr::Set(Some(set)) => set.get_span(),
+            Expr::This(Some(this)) => this.get_span(),
+            Expr::Super(Some(super_expr)) => super_expr.get_span(),
+            Expr::List(Some(list)) => list.get_span(),
+            Expr::Map(Some(map)) => map.get_span(),
+            Expr::Index(Some(index)) => index.get_span(),
+            Expr::SetIndex(Some(set_index)) => set_index.get_span(),
+            Expr::Update(Some(update)) => update.get_span(),
+            _ => Span::default(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Binary {
+    span: Span,
     left: Box<Expr>,
     operator: Token,
     right: Box<Expr>
@@ -21,12 +68,17 @@ pub struct Binary {
 impl Binary {
     pub fn new(left: Box<Expr>, operator: Token, right: Box<Expr>) -> Self {
         Binary {
+            span: left.get_span().to(right.get_span()),
             left,
             operator,
             right
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
     pub fn get_left(&self) -> Box<Expr> {
         self.left.clone()
     }
@@ -41,15 +93,18 @@ impl Binary {
 
     pub fn set_left(&mut self, left: Box<Expr>) {
         self.left = left;
+        self.span = self.left.get_span().to(self.right.get_span());
     }
 
     pub fn set_right(&mut self, right: Box<Expr>) {
         self.right = right;
+        self.span = self.left.get_span().to(self.right.get_span());
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Logical {
+    span: Span,
     left: Box<Expr>,
     operator: Token,
     right: Box<Expr>
@@ -58,12 +113,17 @@ pub struct Logical {
 impl Logical {
     pub fn new(left: Box<Expr>, operator: Token, right: Box<Expr>) -> Self {
         Logical {
+            span: left.get_span().to(right.get_span()),
             left,
             operator,
             right
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
     pub fn get_left(&self) -> Box<Expr> {
         self.left.clone()
     }
@@ -78,15 +138,18 @@ impl Logical {
 
     pub fn set_left(&mut self, left: Box<Expr>) {
         self.left = left;
+        self.span = self.left.get_span().to(self.right.get_span());
     }
 
     pub fn set_right(&mut self, right: Box<Expr>) {
         self.right = right;
+        self.span = self.left.get_span().to(self.right.get_span());
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Unary {
+    span: Span,
     operator: Token,
     expression: Box<Expr>,
 }
@@ -94,11 +157,16 @@ pub struct Unary {
 impl Unary {
     pub fn new(operator: Token, expression: Box<Expr>) -> Self {
         Unary {
+            span: operator.get_span().to(expression.get_span()),
             operator,
             expression
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
     pub fn get_operator(&self) -> Token {
         self.operator.clone()
     }
@@ -109,21 +177,29 @@ impl Unary {
 
     pub fn set_expression(&mut self, expression: Box<Expr>) {
         self.expression = expression;
+        self.span = self.operator.get_span().to(self.expression.get_span());
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Grouping {
+    span: Span,
     expression: Box<Expr>,
 }
 
 impl Grouping {
-    pub fn new(expression: Box<Expr>) -> Self {
+    // `span` covers the parentheses, which are not kept in the tree.
+    pub fn new(expression: Box<Expr>, span: Span) -> Self {
         Grouping {
+            span,
             expression,
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
     pub fn get_expression(&self) -> Box<Expr> {
         self.expression.clone()
     }
@@ -135,16 +211,22 @@ impl Grouping {
 
 #[derive(Clone, Debug)]
 pub struct Literal {
+    span: Span,
     value: Token,
 }
 
 impl Literal {
     pub fn new(value: Token) -> Self {
         Literal {
+            span: value.get_span(),
             value
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
     pub fn get_value(&self) -> Token {
         self.value.clone()
     }
@@ -152,16 +234,28 @@ impl Literal {
 
 #[derive(Clone, Debug)]
 pub struct Variable {
+    span: Span,
+    id: usize,
     value: Token
 }
 
 impl Variable {
     pub fn new(value: Token) -> Self {
         Variable {
+            span: value.get_span(),
+            id: next_id(),
             value
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
     pub fn get_value(&self) -> Token {
         self.value.clone()
     }
@@ -169,6 +263,8 @@ impl Variable {
 
 #[derive(Clone, Debug)]
 pub struct Assign {
+    span: Span,
+    id: usize,
     value: Token,
     expression: Box<Expr>
 }
@@ -176,11 +272,21 @@ pub struct Assign {
 impl Assign {
     pub fn new(value: Token, expression: Box<Expr>) -> Self {
         Assign {
+            span: value.get_span().to(expression.get_span()),
+            id: next_id(),
             value,
             expression
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
     pub fn get_value(&self) -> Token {
         self.value.clone()
     }
@@ -188,4 +294,345 @@ impl Assign {
     pub fn get_expression(&self) -> Box<Expr> {
         self.expression.clone()
     }
-}
\ No newline at end of file
+}
+
+#[derive(Clone, Debug)]
+pub struct Call {
+    span: Span,
+    callee: Box<Expr>,
+    paren: Token,
+    arguments: Vec<Expr>
+}
+
+impl Call {
+    pub fn new(callee: Box<Expr>, paren: Token, arguments: Vec<Expr>) -> Self {
+        Call {
+            span: callee.get_span().to(paren.get_span()),
+            callee,
+            paren,
+            arguments
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_callee(&self) -> Box<Expr> {
+        self.callee.clone()
+    }
+
+    pub fn get_paren(&self) -> Token {
+        self.paren.clone()
+    }
+
+    pub fn get_arguments(&self) -> Vec<Expr> {
+        self.arguments.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Get {
+    span: Span,
+    object: Box<Expr>,
+    name: Token
+}
+
+impl Get {
+    pub fn new(object: Box<Expr>, name: Token) -> Self {
+        Get {
+            span: object.get_span().to(name.get_span()),
+            object,
+            name
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_object(&self) -> Box<Expr> {
+        self.object.clone()
+    }
+
+    pub fn get_name(&self) -> Token {
+        self.name.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Set {
+    span: Span,
+    object: Box<Expr>,
+    name: Token,
+    value: Box<Expr>
+}
+
+impl Set {
+    pub fn new(object: Box<Expr>, name: Token, value: Box<Expr>) -> Self {
+        Set {
+            span: object.get_span().to(value.get_span()),
+            object,
+            name,
+            value
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_object(&self) -> Box<Expr> {
+        self.object.clone()
+    }
+
+    pub fn get_name(&self) -> Token {
+        self.name.clone()
+    }
+
+    pub fn get_value(&self) -> Box<Expr> {
+        self.value.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct This {
+    span: Span,
+    id: usize,
+    keyword: Token
+}
+
+impl This {
+    pub fn new(keyword: Token) -> Self {
+        This {
+            span: keyword.get_span(),
+            id: next_id(),
+            keyword
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_keyword(&self) -> Token {
+        self.keyword.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Super {
+    span: Span,
+    id: usize,
+    keyword: Token,
+    method: Token
+}
+
+impl Super {
+    pub fn new(keyword: Token, method: Token) -> Self {
+        Super {
+            span: keyword.get_span().to(method.get_span()),
+            id: next_id(),
+            keyword,
+            method
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_keyword(&self) -> Token {
+        self.keyword.clone()
+    }
+
+    pub fn get_method(&self) -> Token {
+        self.method.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct List {
+    span: Span,
+    elements: Vec<Expr>
+}
+
+impl List {
+    // `span` covers the brackets, which are not kept in the tree.
+    pub fn new(elements: Vec<Expr>, span: Span) -> Self {
+        List {
+            span,
+            elements
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_elements(&self) -> Vec<Expr> {
+        self.elements.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Map {
+    span: Span,
+    brace: Token,
+    entries: Vec<(Expr, Expr)>
+}
+
+impl Map {
+    // `span` covers the braces.
+    pub fn new(brace: Token, entries: Vec<(Expr, Expr)>, span: Span) -> Self {
+        Map {
+            span,
+            brace,
+            entries
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    // The opening `{`, where runtime errors are reported.
+    pub fn get_brace(&self) -> Token {
+        self.brace.clone()
+    }
+
+    // Key and value expressions, in source order.
+    pub fn get_entries(&self) -> Vec<(Expr, Expr)> {
+        self.entries.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Index {
+    span: Span,
+    object: Box<Expr>,
+    bracket: Token,
+    index: Box<Expr>
+}
+
+impl Index {
+    pub fn new(object: Box<Expr>, bracket: Token, index: Box<Expr>) -> Self {
+        Index {
+            span: object.get_span().to(bracket.get_span()),
+            object,
+            bracket,
+            index
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_object(&self) -> Box<Expr> {
+        self.object.clone()
+    }
+
+    // The closing `]`, where runtime errors are reported.
+    pub fn get_bracket(&self) -> Token {
+        self.bracket.clone()
+    }
+
+    pub fn get_index(&self) -> Box<Expr> {
+        self.index.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SetIndex {
+    span: Span,
+    object: Box<Expr>,
+    bracket: Token,
+    index: Box<Expr>,
+    value: Box<Expr>
+}
+
+impl SetIndex {
+    pub fn new(object: Box<Expr>, bracket: Token, index: Box<Expr>, value: Box<Expr>) -> Self {
+        SetIndex {
+            span: object.get_span().to(value.get_span()),
+            object,
+            bracket,
+            index,
+            value
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_object(&self) -> Box<Expr> {
+        self.object.clone()
+    }
+
+    pub fn get_bracket(&self) -> Token {
+        self.bracket.clone()
+    }
+
+    pub fn get_index(&self) -> Box<Expr> {
+        self.index.clone()
+    }
+
+    pub fn get_value(&self) -> Box<Expr> {
+        self.value.clone()
+    }
+}
+
+// `target op= value`, `++target` or `target++`, where the target is a variable, field
+// or index expression that is evaluated only once. `++` and `--` carry a literal 1.
+#[derive(Clone, Debug)]
+pub struct Update {
+    span: Span,
+    target: Box<Expr>,
+    operator: Token,
+    value: Box<Expr>,
+    postfix: bool
+}
+
+impl Update {
+    pub fn new(target: Box<Expr>, operator: Token, value: Box<Expr>, postfix: bool) -> Self {
+        Update {
+            span: target.get_span().to(operator.get_span()).to(value.get_span()),
+            target,
+            operator,
+            value,
+            postfix
+        }
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn get_target(&self) -> Box<Expr> {
+        self.target.clone()
+    }
+
+    // The `+=`, `++`, ... token, where runtime errors are reported.
+    pub fn get_operator(&self) -> Token {
+        self.operator.clone()
+    }
+
+    pub fn get_value(&self) -> Box<Expr> {
+        self.value.clone()
+    }
+
+    // Whether the expression gives the target's value from before the update.
+    pub fn is_postfix(&self) -> bool {
+        self.postfix
+    }
+}
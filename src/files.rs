@@ -0,0 +1,75 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::native::{native, string_argument};
+use crate::value::Value;
+
+// File system functions, defined as globals. Failures are runtime errors that
+// name the path and the operating system's reason.
+pub fn library() -> Vec<(String, Value)> {
+    vec![
+        native("readFile", 1, |arguments| {
+            let path = string_argument("readFile", &arguments, 0)?;
+
+            match fs::read_to_string(&path) {
+                Ok(content) => Ok(Value::string(content)),
+                Err(e) => Err(failure("read", &path, e)),
+            }
+        }),
+        native("writeFile", 2, |arguments| {
+            let path = string_argument("writeFile", &arguments, 0)?;
+            let content = string_argument("writeFile", &arguments, 1)?;
+
+            match fs::write(&path, content) {
+                Ok(_) => Ok(Value::Nil),
+                Err(e) => Err(failure("write", &path, e)),
+            }
+        }),
+        native("appendFile", 2, |arguments| {
+            let path = string_argument("appendFile", &arguments, 0)?;
+            let content = string_argument("appendFile", &arguments, 1)?;
+
+            let result = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(content.as_bytes()));
+
+            match result {
+                Ok(_) => Ok(Value::Nil),
+                Err(e) => Err(failure("append to", &path, e)),
+            }
+        }),
+        native("fileExists", 1, |arguments| {
+            let path = string_argument("fileExists", &arguments, 0)?;
+
+            Ok(Value::Boolean(Path::new(&path).exists()))
+        }),
+        native("deleteFile", 1, |arguments| {
+            let path = string_argument("deleteFile", &arguments, 0)?;
+
+            match fs::remove_file(&path) {
+                Ok(_) => Ok(Value::Nil),
+                Err(e) => Err(failure("delete", &path, e)),
+            }
+        }),
+        native("listDir", 1, |arguments| {
+            let path = string_argument("listDir", &arguments, 0)?;
+
+            let entries = fs::read_dir(&path).and_then(|entries| entries.collect::<Result<Vec<_>, _>>());
+
+            let mut names: Vec<String> = match entries {
+                Ok(entries) => entries.iter().map(|entry| entry.file_name().to_string_lossy().into_owned()).collect(),
+                Err(e) => return Err(failure("list", &path, e)),
+            };
+            names.sort();
+
+            Ok(Value::list(names.into_iter().map(Value::string).collect()))
+        }),
+    ]
+}
+
+fn failure(action: &str, path: &str, error: std::io::Error) -> String {
+    format!("Could not {} '{}': {}.", action, path, error)
+}
@@ -0,0 +1,75 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::class::Instance;
+use crate::environment::Environment;
+use crate::value::Value;
+use crate::stmt::Stmt;
+use crate::token::Token;
+
+#[derive(Clone)]
+pub struct Function {
+    name: Token,
+    params: Vec<Token>,
+    body: Rc<[Stmt]>,
+    closure: Rc<RefCell<Environment>>,
+    is_initializer: bool,
+}
+
+// The closure usually holds the function itself, so a derived Debug would never end.
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}>", self.name.get_lexeme())
+    }
+}
+
+impl Function {
+    pub fn new(name: Token, params: Vec<Token>, body: Rc<[Stmt]>, closure: Rc<RefCell<Environment>>, is_initializer: bool) -> Self {
+        Function {
+            name,
+            params,
+            body,
+            closure,
+            is_initializer
+        }
+    }
+
+    // Returns a copy of this method whose closure defines `this` as the given instance.
+    pub fn bind(&self, instance: Rc<RefCell<Instance>>) -> Function {
+        let mut environment = Environment::new_with_enclosing(Some(self.get_closure()));
+        environment.define("this".to_string(), Value::Instance(instance));
+
+        Function {
+            name: self.name.clone(),
+            params: self.params.clone(),
+            body: Rc::clone(&self.body),
+            closure: Rc::new(RefCell::new(environment)),
+            is_initializer: self.is_initializer
+        }
+    }
+
+    pub fn get_name(&self) -> Token {
+        self.name.clone()
+    }
+
+    pub fn get_params(&self) -> Vec<Token> {
+        self.params.clone()
+    }
+
+    pub fn get_body(&self) -> Rc<[Stmt]> {
+        Rc::clone(&self.body)
+    }
+
+    pub fn get_closure(&self) -> Rc<RefCell<Environment>> {
+        Rc::clone(&self.closure)
+    }
+
+    pub fn is_initializer(&self) -> bool {
+        self.is_initializer
+    }
+
+    pub fn arity(&self) -> usize {
+        self.params.len()
+    }
+}
@@ -1,170 +1,287 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::token_type::TokenType;
-use crate::token::{LiteralPossibleValues, Token};
+use crate::token::{Span, Token};
 use crate::environment::Environment;
-use crate::error_hadling::runtime_error;
+use crate::error_hadling::{suggest_name, Diagnostic, Phase, StackFrame};
 use crate::expr::*;
+use crate::function::Function;
+use crate::map;
+use crate::native::{self, NativeFunction};
+use crate::class::{Class, Instance};
+use crate::value::Value;
+use crate::operators;
 use crate::stmt::Stmt;
 
-#[derive(Clone)]
-pub enum Value {
-    Boolean(bool),
-    Literal(LiteralPossibleValues)
-}
+// Deepest chain of calls before reporting a stack overflow. Like the VM's `FRAMES_MAX` it
+// counts the top-level script, so both backends overflow at the same depth.
+const MAX_CALL_DEPTH: usize = 1024;
+
+// Native stack, in bytes, that nested calls may use before the interpreter reports a stack
+// overflow. Each Lox call takes around 10 KiB of native stack (about 50 KiB in a debug
+// build), so the default stays inside the 2 MiB Rust gives a spawned thread.
+pub const DEFAULT_STACK_LIMIT: usize = 1024 * 1024;
 
 pub struct Error {
-    token: Option<Token>,
-    message: String
+    token: Option<Box<Token>>,
+    message: String,
+    notes: Vec<String>,
+    // The calls active where the error was raised, innermost first; filled in as it
+    // unwinds out of the innermost one.
+    trace: Option<Vec<StackFrame>>,
+    // The whole expression that failed, when it is wider than `token`.
+    span: Option<Span>
 }
 
 impl Error {
     fn new(token: Option<Token>, message: String) -> Self {
         Error {
-            token,
-            message
+            token: token.map(Box::new),
+            message,
+            notes: Vec::new(),
+            trace: None,
+            span: None
         }
     }
+
+    fn with_note(mut self, note: Option<String>) -> Self {
+        self.notes.extend(note);
+        self
+    }
+
+    fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+}
+
+// How control leaves a statement: normally, unwinding a `return` up to the enclosing
+// call, or leaving the current loop iteration with `break` or `continue`.
+enum ExecutionFlow {
+    Normal,
+    Return(Value),
+    Break,
+    Continue
 }
 
 pub struct Interpreter{
-    pub environment: Environment
+    pub environment: Rc<RefCell<Environment>>,
+    globals: Rc<RefCell<Environment>>,
+    locals: HashMap<usize, usize>,
+    // Functions currently being executed, outermost first.
+    call_stack: Vec<StackFrame>,
+    // Address near the bottom of the native stack used by the current run, and how far
+    // past it calls may go.
+    stack_base: usize,
+    stack_limit: usize,
+    diagnostics: Vec<Diagnostic>
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+
+        for (name, value) in native::standard_library() {
+            globals.borrow_mut().define(name, value);
+        }
+
         Interpreter {
-            environment: Environment::new()
+            environment: Rc::clone(&globals),
+            globals,
+            locals: HashMap::new(),
+            call_stack: Vec::new(),
+            stack_base: 0,
+            stack_limit: DEFAULT_STACK_LIMIT,
+            diagnostics: Vec::new()
+        }
+    }
+
+    // Exposes a Rust function to scripts as the global `name`.
+    pub fn define_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(Vec<Value>) -> Result<Value, String> + 'static,
+    {
+        let native = NativeFunction::new(name, arity, function);
+        self.globals.borrow_mut().define(name.to_string(), Value::NativeFunction(Rc::new(native)));
+    }
+
+    // Sets how many bytes of native stack nested calls may use; see `DEFAULT_STACK_LIMIT`.
+    pub fn set_stack_limit(&mut self, bytes: usize) {
+        self.stack_limit = bytes;
+    }
+
+    // Receives the scope depths computed by the resolver.
+    pub fn resolve(&mut self, locals: HashMap<usize, usize>) {
+        self.locals.extend(locals);
+    }
+
+    // Runtime errors raised since the last call, oldest first.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    // Runs the statements until the first runtime error, which is recorded as a diagnostic.
+    // Returns whether every statement ran.
+    pub fn interpret(&mut self, statements: Vec<Stmt>) -> bool {
+        self.mark_stack_base();
+
+        match self.execute_statements(&statements) {
+            Ok(_) => true,
+            Err(e) => {
+                self.handle_error_result(e);
+                false
+            },
+        }
+    }
+
+    // Evaluates a single expression, reporting a runtime error and giving nil if it fails.
+    pub fn evaluate(&mut self, expression: Expr) -> Value {
+        self.mark_stack_base();
+
+        match self.get_expression_value(expression) {
+            Ok(value) => value,
+            Err(e) => {
+                self.handle_error_result(e);
+                Value::Nil
+            },
         }
     }
 
-    pub fn interpret(&mut self, statements: Vec<Stmt>) {
+    fn execute_statements(&mut self, statements: &[Stmt]) -> Result<ExecutionFlow, Error> {
         for statement in statements {
             match statement {
+                Stmt::Class(name, superclass, methods) => {
+                    self.define_class(name.clone(), superclass.clone(), methods)?;
+                },
                 Stmt::Expr(expr) => {
-                    let result = self.get_expression_value(expr);
-                    match result {
-                        Ok(_) => {},
-                        Err(e) => self.handle_error_result(e),
-                    }
+                    self.get_expression_value(expr.clone())?;
                 },
-                Stmt::If(condition, then_statement, else_statement) => {
-                    let result_condition_value = self.get_expression_value(condition);
-                    let mut condition_value: Option<Value> = None;
-
-                    match result_condition_value {
-                        Ok(value) => {
-                            condition_value = value;
-                        },
-                        Err(e) => self.handle_error_result(e),
-                    }
+                Stmt::Function(name, params, body) => {
+                    let function = Function::new(name.clone(), params.clone(), Rc::clone(body), Rc::clone(&self.environment), false);
+                    self.environment.borrow_mut().define(name.get_lexeme(), Value::Function(Rc::new(function)));
+                },
+                Stmt::If(_, condition, then_statement, else_statement) => {
+                    let condition_value = self.get_expression_value(condition.clone())?;
 
-                    if self.is_truthy(&condition_value) {
-                        self.interpret(vec![*then_statement]);
+                    let flow = if operators::is_truthy(&condition_value) {
+                        self.execute_statements(std::slice::from_ref(then_statement.as_ref()))?
+                    }
+                    else if let Some(else_stmt) = else_statement {
+                        self.execute_statements(std::slice::from_ref(else_stmt.as_ref()))?
                     }
                     else {
-                        if let Some(else_stmt) = else_statement {
-                            self.interpret(vec![*else_stmt]);
-                        }
+                        ExecutionFlow::Normal
+                    };
+
+                    if !matches!(flow, ExecutionFlow::Normal) {
+                        return Ok(flow);
                     }
                 },
-                Stmt::While(condition, body) => {
-                    let expression_value = self.get_expression_value(condition.clone());
-                    let body = *body;
-
-                    match expression_value {
-                        Ok(value) => {
-                            let mut value = self.is_truthy(&value);
-                            while value {
-                                self.interpret(vec![body.clone()]);
-                                let new_value = self.get_expression_value(condition.clone());
-                                match new_value {
-                                    Ok(val) => {
-                                        let new_value = val;
-                                        value = self.is_truthy(&new_value);
-                                    },
-                                    Err(e) => self.handle_error_result(e),
-                                }
-                                
-                            }
-                        },
-                        Err(e) => self.handle_error_result(e),
+                Stmt::While(_, condition, body, increment) => {
+                    while operators::is_truthy(&self.get_expression_value(condition.clone())?) {
+                        let flow = self.execute_statements(std::slice::from_ref(body.as_ref()))?;
+                        match flow {
+                            ExecutionFlow::Return(_) => return Ok(flow),
+                            ExecutionFlow::Break => break,
+                            ExecutionFlow::Normal | ExecutionFlow::Continue => {},
+                        }
+
+                        if let Some(expr) = increment {
+                            self.get_expression_value(expr.clone())?;
+                        }
                     }
                 },
                 Stmt::Block(block) => {
-                    self.execute_block(block);
+                    let environment = Environment::new_with_enclosing(Some(Rc::clone(&self.environment)));
+                    let flow = self.execute_block(block, environment)?;
+                    if !matches!(flow, ExecutionFlow::Normal) {
+                        return Ok(flow);
+                    }
                 },
-                Stmt::Print(expr) => {
-                    let result = self.get_expression_value(expr);
-                    match result {
-                        Ok(value) => self.handle_ok_result(value),
-                        Err(e) => self.handle_error_result(e),
+                Stmt::Break(_) => return Ok(ExecutionFlow::Break),
+                Stmt::Continue(_) => return Ok(ExecutionFlow::Continue),
+                Stmt::Print(_, expr) => {
+                    let value = self.get_expression_value(expr.clone())?;
+                    self.handle_ok_result(value);
+                },
+                Stmt::Return(_, value) => {
+                    let mut return_value = Value::Nil;
+
+                    if let Some(expr) = value {
+                        return_value = self.get_expression_value(expr.clone())?;
                     }
+
+                    return Ok(ExecutionFlow::Return(return_value));
                 },
-                Stmt::Var(name, value) => {
-                    self.environment.define(name.get_lexeme(), value);
+                Stmt::Var(_, name, value) => {
+                    let mut initial_value = Value::Nil;
+
+                    if let Some(expr) = value {
+                        initial_value = self.get_expression_value(expr.clone())?;
+                    }
+
+                    self.environment.borrow_mut().define(name.get_lexeme(), initial_value);
                 },
             }
         }
+
+        Ok(ExecutionFlow::Normal)
     }
     
-    fn handle_ok_result(&self, value: Option<Value>) {
-        if let Some(val) = value {
-            match val {
-                Value::Boolean(val) => self.print_boolean(val),
-                Value::Literal(literal_value) => self.print_literal(literal_value),
-            }
-        } else {
-            println!("Nil");
-        }
-    }
-    
-    fn print_boolean(&self, val: bool) {
-        if val {
-            println!("true");
-        } else {
-            println!("false");
-        }
-    }
-    
-    fn print_literal(&self, literal_value: LiteralPossibleValues) {
-        match literal_value {
-            LiteralPossibleValues::StringValue(string_value) => println!("{}", string_value),
-            LiteralPossibleValues::DoubleValue(double_value) => println!("{}", double_value),
-        }
+    fn handle_ok_result(&self, value: Value) {
+        println!("{}", value);
     }
     
-    fn handle_error_result(&self, e: Error) {
+    fn handle_error_result(&mut self, e: Error) {
         match e.token {
-            Some(token) => runtime_error(token, e.message),
+            Some(token) => {
+                let mut diagnostic = Diagnostic::error_at(Phase::Runtime, &token, e.message);
+                if let Some(span) = e.span {
+                    diagnostic = diagnostic.with_span(span);
+                }
+                for note in e.notes {
+                    diagnostic = diagnostic.with_note(note);
+                }
+
+                let trace = e.trace.unwrap_or_else(|| self.call_stack.iter().rev().cloned().collect());
+                self.diagnostics.push(diagnostic.with_trace(trace));
+            },
             None => panic!("{}", e.message),
         }
     }
 
-    fn execute_block(&mut self, block: Vec<Stmt>) {
-        // Salva o ambiente atual em 'previous'
-        let previous = Rc::new(RefCell::new(self.environment.clone()));
-    
-        // Cria um novo ambiente e o define como o atual
-        self.environment = Environment::new();
-        self.environment.set_enclosing_environment(Some(Rc::clone(&previous)));
+    fn execute_block(&mut self, block: &[Stmt], environment: Environment) -> Result<ExecutionFlow, Error> {
+        // Salva o ambiente atual em 'previous' e ativa o novo ambiente.
+        // Os ambientes são compartilhados, então funções que capturaram
+        // algum deles continuam vendo as alterações feitas aqui.
+        let previous = Rc::clone(&self.environment);
+        self.environment = Rc::new(RefCell::new(environment));
     
         // Interpreta o bloco de declarações
-        self.interpret(block);
+        let flow = self.execute_statements(block);
     
         // Restaura o ambiente anterior
-        self.environment = previous.borrow().clone();
+        self.environment = previous;
+
+        flow
     }
 
-    pub fn get_expression_value(&mut self, expression: Expr) -> Result<Option<Value>, Error> {
+    pub fn get_expression_value(&mut self, expression: Expr) -> Result<Value, Error> {
         match expression {
             Expr::Literal(value) => {
                 if let Some(val) = value {
                     return self.get_literal_value(val);
                 }
                 else {
-                    let error_value = Error::new(None, "[ERROR] Value does not exist".to_string());
+                    let error_value = Error::new(None, "Value does not exist".to_string());
                     return Err(error_value);
                 }
             },
@@ -173,7 +290,7 @@ impl Interpreter {
                     return self.get_group(val);
                 }
                 else {
-                    let error_value = Error::new(None, "[ERROR] Group does not exist".to_string());
+                    let error_value = Error::new(None, "Group does not exist".to_string());
                     return Err(error_value);
                 }
             },
@@ -182,7 +299,7 @@ impl Interpreter {
                     return self.get_unary(val);
                 }
                 else {
-                    let error_value = Error::new(None, "[ERROR] Unary expression does not exist".to_string());
+                    let error_value = Error::new(None, "Unary expression does not exist".to_string());
                     return Err(error_value);
                 }
             },
@@ -191,7 +308,7 @@ impl Interpreter {
                     return self.get_binary_expression_result_value(val);
                 }
                 else {
-                    let error_value = Error::new(None, "[ERROR] Binary expression does not exist".to_string());
+                    let error_value = Error::new(None, "Binary expression does not exist".to_string());
                     return Err(error_value);
                 }
             }
@@ -200,7 +317,7 @@ impl Interpreter {
                     return self.get_variable_value(val);
                 }
                 else {
-                    let error_value = Error::new(None, "[ERROR] Variable expression error".to_string());
+                    let error_value = Error::new(None, "Variable expression error".to_string());
                     return Err(error_value);
                 }
             },
@@ -209,7 +326,7 @@ impl Interpreter {
                     return self.get_assign_value(val);
                 }
                 else {
-                    let error_value = Error::new(None, "[ERROR] Assign expression error".to_string());
+                    let error_value = Error::new(None, "Assign expression error".to_string());
                     return Err(error_value);
                 }
             }
@@ -218,22 +335,153 @@ impl Interpreter {
                     return self.get_logical_value(val);
                 }
                 else {
-                    let error_value = Error::new(None, "[ERROR] Logical expression error".to_string());
+                    let error_value = Error::new(None, "Logical expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::Call(value) => {
+                if let Some(val) = value {
+                    return self.get_call_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "Call expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::Get(value) => {
+                if let Some(val) = value {
+                    return self.get_property_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "Get expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::Set(value) => {
+                if let Some(val) = value {
+                    return self.get_set_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "Set expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::List(value) => {
+                if let Some(val) = value {
+                    return self.get_list_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "List expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::Map(value) => {
+                if let Some(val) = value {
+                    return self.get_map_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "Map expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::Index(value) => {
+                if let Some(val) = value {
+                    return self.get_index_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "Index expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::SetIndex(value) => {
+                if let Some(val) = value {
+                    return self.get_set_index_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "Index assignment error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::Update(value) => {
+                if let Some(val) = value {
+                    return self.get_update_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "Update expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::Super(value) => {
+                if let Some(val) = value {
+                    return self.get_super_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "Super expression error".to_string());
+                    return Err(error_value);
+                }
+            },
+            Expr::This(value) => {
+                if let Some(val) = value {
+                    return self.get_this_value(val);
+                }
+                else {
+                    let error_value = Error::new(None, "This expression error".to_string());
                     return Err(error_value);
                 }
             },
         }
     }
 
-    fn get_literal_value(&self, val: Literal) -> Result<Option<Value>, Error> {
+    fn get_list_value(&mut self, list: List) -> Result<Value, Error> {
+        let mut elements: Vec<Value> = Vec::new();
+        for element in list.get_elements() {
+            elements.push(self.get_expression_value(element)?);
+        }
+
+        Ok(Value::list(elements))
+    }
+
+    fn get_map_value(&mut self, map: Map) -> Result<Value, Error> {
+        let mut entries = map::Map::new();
+        for (key, value) in map.get_entries() {
+            let key_span = key.get_span();
+            let key = self.get_expression_value(key)?;
+            let value = self.get_expression_value(value)?;
+            entries.insert(key, value).map_err(|e| Error::new(Some(map.get_brace()), e).with_span(key_span))?;
+        }
+
+        Ok(Value::map(entries))
+    }
+
+    fn get_index_value(&mut self, index: Index) -> Result<Value, Error> {
+        let object = self.get_expression_value(*index.get_object())?;
+        let position = self.get_expression_value(*index.get_index())?;
+
+        operators::get_index(object, position).map_err(|e| Error::new(Some(index.get_bracket()), e).with_span(index.get_span()))
+    }
+
+    fn get_set_index_value(&mut self, set_index: SetIndex) -> Result<Value, Error> {
+        let object = self.get_expression_value(*set_index.get_object())?;
+        let position = self.get_expression_value(*set_index.get_index())?;
+        let new_value = self.get_expression_value(*set_index.get_value())?;
+
+        operators::set_index(object, position, new_value).map_err(|e| Error::new(Some(set_index.get_bracket()), e).with_span(set_index.get_span()))
+    }
+
+    fn get_this_value(&self, this: This) -> Result<Value, Error> {
+        let result = self.look_up_variable(this.get_keyword(), this.get_id());
+        result.map_err(|e| Error::new(Some(this.get_keyword()), e))
+    }
+
+    fn get_literal_value(&self, val: Literal) -> Result<Value, Error> {
         match val.get_value().get_token_type() {
             TokenType::Number => {
                 match val.get_value().get_literal() {
                     Some(value) => {
-                        return Ok(Some(Value::Literal(value)))
+                        return Ok(Value::Literal(value))
                     },
                     None => {
-                        let error_value = Error::new(Some(val.get_value()), "[ERROR] The Token is a Number, but his value is nil.".to_string());
+                        let error_value = Error::new(Some(val.get_value()), "The Token is a Number, but his value is nil.".to_string());
                         return Err(error_value);
                     }
                 };
@@ -242,541 +490,388 @@ impl Interpreter {
             TokenType::String => {
                 match val.get_value().get_literal() {
                     Some(value) => {
-                        return Ok(Some(Value::Literal(value)))
+                        return Ok(Value::Literal(value))
                     },
                     None => {
-                        let error_value = Error::new(Some(val.get_value()), "[ERROR] The Token is a String, but his value is nil.".to_string());
+                        let error_value = Error::new(Some(val.get_value()), "The Token is a String, but his value is nil.".to_string());
                         return Err(error_value);
                     }
                 };
             },
 
             TokenType::True => {
-                return Ok(Some(Value::Boolean(true)));
+                return Ok(Value::Boolean(true));
             },
 
             TokenType::False => {
-                return Ok(Some(Value::Boolean(false)));
+                return Ok(Value::Boolean(false));
             },
 
             TokenType::Nil => {
-                return Ok(None);
+                return Ok(Value::Nil);
             }
 
-            _ => return Err(Error::new(Some(val.get_value()), "[ERROR] The Token is not a literal.".to_string())),
+            _ => return Err(Error::new(Some(val.get_value()), "The Token is not a literal.".to_string())),
         }
     }
 
-    fn get_variable_value(&mut self, variable: Variable) -> Result<Option<Value>, Error> {
-        let result = self.environment.get(variable.get_value());
+    fn get_variable_value(&mut self, variable: Variable) -> Result<Value, Error> {
+        let result = self.look_up_variable(variable.get_value(), variable.get_id());
         
         match result {
-            Ok(value) => {
-                match value {
-                    Some(val) => {
-                        self.get_expression_value(val)
-                    },
-                    None => Err(Error::new(Some(variable.get_value()), format!("[ERROR] Variable {} is not defined!", variable.get_value().get_lexeme()))),
-                }
+            Ok(value) => Ok(value),
+            Err(e) => {
+                let note = self.suggest_variable(&variable.get_value());
+                Err(Error::new(Some(variable.get_value()), e).with_note(note))
             },
-            Err(e) => Err(Error::new(Some(variable.get_value()), format!("{}",e))),
         }
     }
 
-    fn get_assign_value(&mut self, assign: Assign) -> Result<Option<Value>, Error> {
-        let result = self.environment.get(assign.get_value());
-        
-        match result {
-            Ok(_) => {
-                let final_expression = self.get_final_expression_in_assign(*assign.get_expression())?;
-
-                let result = self.environment.assign(assign.get_value().get_lexeme(), Some(final_expression.clone()));
-
-                match result {
-                    Ok(_) => {},
-                    Err(e) => return Err(Error::new(Some(assign.get_value()), format!("{}",e))),
-                }
-
-                let expression_result = self.get_expression_value(*assign.get_expression())?;
-
-                return Ok(expression_result);
-            },
-            Err(e) => Err(Error::new(Some(assign.get_value()), format!("{}",e))),
-        }
+    // A "did you mean" hint for a name that is not defined in any visible scope.
+    fn suggest_variable(&self, name: &Token) -> Option<String> {
+        let names = self.environment.borrow().get_names();
+        suggest_name(&name.get_lexeme(), &names).map(|suggestion| format!("did you mean `{}`?", suggestion))
     }
 
-    fn get_final_expression_in_assign(&self, expression: Expr) -> Result<Expr, Error> {
-        match expression {
-            Expr::Variable(variable_value) => {
-                match variable_value {
-                    Some(value) => {
-                        let result = self.environment.get(value.get_value());
-                        match result {
-                            Ok(expression_value) => {
-                                return Ok(expression_value.unwrap());
-                            },
-                            Err(e) => Err(Error::new(Some(value.get_value()), e)),
-                        }
-                    },
-                    None => Err(Error::new(None, "[ERROR] There is no variable expression to interpret".to_string())),
-                }
-            },
-            Expr::Assign(assign_value) => {
-                match assign_value {
-                    Some(value) => {
-                        return Ok(self.get_final_expression_in_assign(*value.get_expression())?);
-                    },
-                    None => Err(Error::new(None, "[ERROR] There is no assign expression to interpret".to_string())),
-                }
-            },
-            _ => {
-                return self.get_expression_tree_without_variables(expression)
-            },
+    fn look_up_variable(&self, name: Token, id: usize) -> Result<Value, String> {
+        match self.locals.get(&id) {
+            Some(distance) => self.environment.borrow().get_at(*distance, name),
+            None => self.globals.borrow().get(name),
         }
     }
 
-    fn get_expression_tree_without_variables(&self, expression: Expr) -> Result<Expr, Error> {
-        match expression.clone() {
-            Expr::Literal(_) => return Ok(expression),
-            Expr::Grouping(group) => {
-                if let Some(mut group_value) = group {
-                    let expression_group = *group_value.get_expression();
-                    let result = self.get_expression_tree_without_variables(expression_group)?;
-                    let result = Box::new(result);
+    fn get_assign_value(&mut self, assign: Assign) -> Result<Value, Error> {
+        let value = self.get_expression_value(*assign.get_expression())?;
+        self.assign_variable(assign.get_value(), assign.get_id(), value.clone())?;
 
-                    group_value.set_expression(result);
-                    return Ok(Expr::Grouping(Some(group_value)));
-                }
-                return Ok(expression);
-            },
-            Expr::Logical(logical) => {
-                if let Some(mut logical_value) = logical {
-                    let left = *logical_value.get_left();
-                    let left = self.get_expression_tree_without_variables(left)?;
-                    let result = Box::new(left);
+        Ok(value)
+    }
 
-                    logical_value.set_left(result);
+    fn assign_variable(&mut self, name: Token, id: usize, value: Value) -> Result<(), Error> {
+        let result = match self.locals.get(&id) {
+            Some(distance) => self.environment.borrow_mut().assign_at(*distance, name.get_lexeme(), value),
+            None => self.globals.borrow_mut().assign(name.get_lexeme(), value),
+        };
 
-                    let right = *logical_value.get_right();
-                    let right = self.get_expression_tree_without_variables(right)?;
-                    let result = Box::new(right);
+        result.map_err(|e| {
+            let note = self.suggest_variable(&name);
+            Error::new(Some(name), e).with_note(note)
+        })
+    }
 
-                    logical_value.set_right(result);
+    // Reads the target once, combines it with the value and stores the result back.
+    fn get_update_value(&mut self, update: Update) -> Result<Value, Error> {
+        let operator = update.get_operator();
 
-                    return Ok(Expr::Logical(Some(logical_value)));
-                }
-                return Ok(expression);
-            },
-            Expr::Unary(unary) => {
-                if let Some(mut unary_value) = unary {
-                    let unary_expression = *unary_value.get_expression();
-                    let result = self.get_expression_tree_without_variables(unary_expression)?;
-                    let result = Box::new(result);
+        let (old, new) = match *update.get_target() {
+            Expr::Variable(Some(variable)) => {
+                let old = self.get_variable_value(variable.clone())?;
+                let new = self.apply_update(&update, old.clone())?;
+                self.assign_variable(variable.get_value(), variable.get_id(), new.clone())?;
 
-                    unary_value.set_expression(result);
-                    return Ok(Expr::Unary(Some(unary_value)));
-                }
-                return Ok(expression);
+                (old, new)
             },
-            Expr::Binary(binary) => {
-                if let Some(mut binary_value) = binary {
-                    let left = *binary_value.get_left();
-                    let left = self.get_expression_tree_without_variables(left)?;
-                    let result = Box::new(left);
+            Expr::Get(Some(get)) => {
+                let object = self.get_expression_value(*get.get_object())?;
+                let old = self.property(object.clone(), get.get_name()).map_err(|e| e.with_span(get.get_span()))?;
+                let new = self.apply_update(&update, old.clone())?;
 
-                    binary_value.set_left(result);
+                match object {
+                    Value::Instance(instance) => instance.borrow_mut().set(&get.get_name(), new.clone()),
+                    _ => return Err(Error::new(Some(get.get_name()), "Only instances have fields.".to_string()).with_span(update.get_span())),
+                }
 
-                    let right = *binary_value.get_right();
-                    let right = self.get_expression_tree_without_variables(right)?;
-                    let result = Box::new(right);
+                (old, new)
+            },
+            Expr::Index(Some(index)) => {
+                let object = self.get_expression_value(*index.get_object())?;
+                let position = self.get_expression_value(*index.get_index())?;
 
-                    binary_value.set_right(result);
+                let old = operators::get_index(object.clone(), position.clone())
+                    .map_err(|e| Error::new(Some(index.get_bracket()), e).with_span(index.get_span()))?;
+                let new = self.apply_update(&update, old.clone())?;
+                operators::set_index(object, position, new.clone())
+                    .map_err(|e| Error::new(Some(index.get_bracket()), e).with_span(update.get_span()))?;
 
-                    return Ok(Expr::Binary(Some(binary_value)));
-                }
-                return Ok(expression);
-            },
-            Expr::Variable(variable_value) => {
-                match variable_value {
-                    Some(value) => {
-                        let result = self.environment.get(value.get_value());
-                        match result {
-                            Ok(expression_value) => {
-                                return Ok(expression_value.unwrap());
-                            },
-                            Err(e) => Err(Error::new(Some(value.get_value()), e)),
-                        }
-                    },
-                    None => Err(Error::new(None, "[ERROR] There is no variable expression to interpret".to_string())),
-                }
+                (old, new)
             },
-            Expr::Assign(_) => return Ok(expression),
-        }
-    }
+            _ => return Err(Error::new(Some(operator), "Invalid assignment target.".to_string())),
+        };
 
-    fn get_group(&mut self, group: Grouping) -> Result<Option<Value>, Error> {
-        return self.get_expression_value(*group.get_expression().clone());
+        Ok(if update.is_postfix() { old } else { new })
     }
 
-    fn get_unary(&mut self, expression: Unary) -> Result<Option<Value>, Error> {
-        let expression_result = self.get_expression_value(*expression.get_expression().clone())?;
-        let operator = expression.get_operator().get_token_type();
-
-        match operator {
-            TokenType::Minus => {
-                if let Some(Value::Literal(value)) = expression_result {
-                    if let LiteralPossibleValues::DoubleValue(val) = value {
-                        return Ok(Some(Value::Literal(LiteralPossibleValues::DoubleValue(-val))));
-                    }
-                    else {
-                        let error_value = Error::new(Some(expression.get_operator()), "[ERROR] Cannot use the '-' operator in a string".to_string());
-                        return Err(error_value);
-                    }
-                }
-                else {
-                    let error_value = Error::new(Some(expression.get_operator()), "[ERROR] Cannot use the '-' operator in a nil".to_string());
-                    return Err(error_value);
-                }
-            },
+    fn apply_update(&mut self, update: &Update, old: Value) -> Result<Value, Error> {
+        let value = self.get_expression_value(*update.get_value())?;
+        let operator = update.get_operator();
 
-            TokenType::Bang => {
-                Ok(Some(Value::Boolean(!self.is_truthy(&expression_result))))
-            },
+        let result = match operator.get_token_type() {
+            TokenType::PlusEqual | TokenType::PlusPlus => operators::sum(old, value),
+            TokenType::MinusEqual | TokenType::MinusMinus => operators::subtract(old, value),
+            TokenType::StarEqual => operators::multiply(old, value),
+            TokenType::SlashEqual => operators::divide(old, value),
+            _ => Err("The token is not an assignment operator.".to_string()),
+        };
 
-            _ => return Err(Error::new(Some(expression.get_operator()), "[ERROR] The token is unary, but do not have an unary operator!".to_string()))
-        }
+        result.map_err(|message| Error::new(Some(operator), message).with_span(update.get_span()))
     }
 
-    fn is_truthy(&self, value: &Option<Value>) -> bool {
-        match value {
-            Some(val) => {
-                match val {
-                    Value::Boolean(value1) => *value1,
-                    Value::Literal(_) => true,
-                }
-            },
-            None => false
-        }
-    }
+    fn get_call_value(&mut self, call: Call) -> Result<Value, Error> {
+        let callee = self.get_expression_value(*call.get_callee())?;
 
-    fn get_logical_value(&mut self, value:Logical) -> Result<Option<Value>, Error> {
-        let left = self.get_expression_value(*value.get_left().clone())?;
-        let right = self.get_expression_value(*value.get_right().clone())?;
-        let operator = value.get_operator().get_token_type();
+        let mut arguments: Vec<Value> = Vec::new();
+        for argument in call.get_arguments() {
+            arguments.push(self.get_expression_value(argument)?);
+        }
 
-        match operator {
-            TokenType::Or => {
-                if self.is_truthy(&left) {
-                    return Ok(left);
+        match callee {
+            Value::Function(function) => {
+                if arguments.len() != function.arity() {
+                    let message = format!("Expected {} arguments but got {}.", function.arity(), arguments.len());
+                    return Err(Error::new(Some(call.get_paren()), message).with_span(call.get_span()));
                 }
+
+                self.call_function(function, arguments, &call)
             },
-            TokenType::And => {
-                if !self.is_truthy(&left) {
-                    return Ok(left);
+            Value::Class(class) => {
+                if arguments.len() != class.arity() {
+                    let message = format!("Expected {} arguments but got {}.", class.arity(), arguments.len());
+                    return Err(Error::new(Some(call.get_paren()), message).with_span(call.get_span()));
                 }
-            },
-            _ => return Err(Error::new(Some(value.get_operator()), "[ERROR] The Operator is not a logical operator.".to_string()))
-        }
-
-        Ok(right)
-    }
 
-    fn get_binary_expression_result_value(&mut self, value: Binary) -> Result<Option<Value>, Error> {
-        let left = self.get_expression_value(*value.get_left().clone())?;
-        let right = self.get_expression_value(*value.get_right().clone())?;
-        let operator = value.get_operator().get_token_type();
+                let instance = Rc::new(RefCell::new(Instance::new(Rc::clone(&class))));
 
-        match operator {
-            TokenType::Minus => {
-                let result = self.subtract(left, right);
-                match result {
-                    Ok(value) => Ok(value),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
+                if let Some(initializer) = class.find_method("init") {
+                    let initializer = Rc::new(initializer.bind(Rc::clone(&instance)));
+                    self.call_function(initializer, arguments, &call)?;
                 }
-            },
-            TokenType::Star => {
-                let result = self.multiply(left, right);
-                match result {
-                    Ok(value) => Ok(value),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
-                }
-            },
-            TokenType::Slash => {
-                let result = self.divide(left, right);
-                match result {
-                    Ok(value) => Ok(value),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
-                }
-            },
-            TokenType::Plus => {
-                let result = self.sum(left, right);
-                match result {
-                    Ok(value) => Ok(value),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
-                }
-            },
 
-            TokenType::Greater => {
-                let result = self.greater(left, right);
-                match result {
-                    Ok(value) => Ok(value),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
-                }
-            },
-            TokenType::GreaterEqual => {
-                let result = self.greater_equal(left, right);
-                match result {
-                    Ok(value) => Ok(value),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
-                }
+                Ok(Value::Instance(instance))
             },
-            TokenType::Less => {
-                let result = self.less(left, right);
-                match result {
-                    Ok(value) => Ok(value),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
+            Value::NativeFunction(native) => {
+                if arguments.len() != native.arity() {
+                    let message = format!("Expected {} arguments but got {}.", native.arity(), arguments.len());
+                    return Err(Error::new(Some(call.get_paren()), message).with_span(call.get_span()));
                 }
-            },
-            TokenType::LessEqual => {
-                let result = self.less_equal(left, right);
-                match result {
-                    Ok(value) => Ok(value),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
-                }
-            },
 
-            TokenType::BangEqual => {
-                let result = self.is_equal(left, right);
-                match result {
-                    Ok(value) => Ok(Some(Value::Boolean(!value))),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
-                }
-            },
-            TokenType::EqualEqual => {
-                let result = self.is_equal(left, right);
-                match result {
-                    Ok(value) => Ok(Some(Value::Boolean(value))),
-                    Err(message) => Err(Error::new(Some(value.get_operator()), message)),
-                }
+                native.call(arguments).map_err(|message| Error::new(Some(call.get_paren()), message).with_span(call.get_span()))
             },
-            _ => Err(Error::new(Some(value.get_operator()), "[ERROR] Operator does not exist!".to_string()))
+            _ => Err(Error::new(Some(call.get_paren()), "Can only call functions and classes.".to_string()).with_span(call.get_span())),
         }
     }
 
-    fn subtract(&self, left: Option<Value>, right: Option<Value>) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (Some(value_left), Some(value_right)) => {
+    fn mark_stack_base(&mut self) {
+        let marker = 0u8;
+        self.stack_base = &marker as *const u8 as usize;
+    }
 
-                match (value_left, value_right) {
-                    (Value::Literal(literal_left), Value::Literal(literal_right)) => {
-                        return self.subtract_aux(literal_left, literal_right);
-                    }
-                    _ => Err("[ERROR] Cannot subtract boolean values".to_string()),
-                }
+    fn native_stack_used(&self) -> usize {
+        let marker = 0u8;
+        self.stack_base.abs_diff(&marker as *const u8 as usize)
+    }
 
-            }
-            _ => Err("[ERROR] Cannot subtract nil values".to_string())
+    fn call_function(&mut self, function: Rc<Function>, arguments: Vec<Value>, call: &Call) -> Result<Value, Error> {
+        let paren = call.get_paren();
+        if self.call_stack.len() + 1 >= MAX_CALL_DEPTH || self.native_stack_used() > self.stack_limit {
+            return Err(Error::new(Some(paren), "Stack overflow.".to_string()).with_span(call.get_span()));
         }
-    }
 
-    fn subtract_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (LiteralPossibleValues::DoubleValue(number_left), LiteralPossibleValues::DoubleValue(number_right)) => {
-                return Ok(Some(Value::Literal(LiteralPossibleValues::DoubleValue(number_left - number_right))));
-            },
-            _ => Err("[ERROR] Cannot subtract string values".to_string()),
+        let mut environment = Environment::new_with_enclosing(Some(function.get_closure()));
+
+        for (param, argument) in function.get_params().iter().zip(arguments) {
+            environment.define(param.get_lexeme(), argument);
         }
-    }
 
-    fn multiply(&self, left: Option<Value>, right: Option<Value>) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (Some(value_left), Some(value_right)) => {
-                match (value_left, value_right) {
-                    (Value::Literal(literal_left), Value::Literal(literal_right)) => {
-                        return self.multiply_aux(literal_left, literal_right);
-                    },
-                    _ => Err("[ERROR] Cannot multiply boolean values".to_string())
+        self.call_stack.push(StackFrame::new(function.get_name().get_lexeme(), paren.get_line()));
+        let result = self.execute_block(&function.get_body(), environment);
+
+        let flow = match result {
+            Ok(flow) => flow,
+            Err(mut e) => {
+                if e.trace.is_none() {
+                    e.trace = Some(self.call_stack.iter().rev().cloned().collect());
                 }
+                self.call_stack.pop();
+                return Err(e);
             },
-            _ => Err("[ERROR] Cannot multiply nil expressions.".to_string())
+        };
+        self.call_stack.pop();
+
+        if function.is_initializer() {
+            return Ok(function.get_closure().borrow().values.get("this").cloned().unwrap_or(Value::Nil));
         }
-    }
 
-    fn multiply_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (LiteralPossibleValues::DoubleValue(number_left), LiteralPossibleValues::DoubleValue(number_right)) => {
-                return Ok(Some(Value::Literal(LiteralPossibleValues::DoubleValue(number_left * number_right))));
-            },
-            _ => Err("[ERROR] Cannot multiply string values".to_string()),
+        match flow {
+            ExecutionFlow::Return(value) => Ok(value),
+            _ => Ok(Value::Nil),
         }
     }
 
-    fn divide(&self, left: Option<Value>, right: Option<Value>) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (Some(value_left), Some(value_right)) => {
-                match (value_left, value_right) {
-                    (Value::Literal(literal_left), Value::Literal(literal_right)) => {
-                        return self.divide_aux(literal_left, literal_right);
-                    },
-                    _ => Err("[ERROR] Cannot divide boolean values".to_string())
-                }
-            },
-            _ => Err("[ERROR] Cannot divide nil expressions.".to_string())
+    fn define_class(&mut self, name: Token, superclass: Option<Expr>, methods: &[Stmt]) -> Result<(), Error> {
+        let mut superclass_value: Option<Rc<Class>> = None;
+
+        if let Some(Expr::Variable(Some(superclass_variable))) = superclass {
+            let superclass_name = superclass_variable.get_value();
+
+            if superclass_name.get_lexeme() == name.get_lexeme() {
+                return Err(Error::new(Some(superclass_name), "A class can't inherit from itself.".to_string()));
+            }
+
+            match self.get_variable_value(superclass_variable)? {
+                Value::Class(class) => superclass_value = Some(class),
+                _ => return Err(Error::new(Some(superclass_name), "Superclass must be a class.".to_string())),
+            }
         }
-    }
 
-    fn divide_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (LiteralPossibleValues::DoubleValue(number_left), LiteralPossibleValues::DoubleValue(number_right)) => {
-                return Ok(Some(Value::Literal(LiteralPossibleValues::DoubleValue(number_left / number_right))));
-            },
-            _ => Err("[ERROR] Cannot divide string values".to_string()),
+        self.environment.borrow_mut().define(name.get_lexeme(), Value::Nil);
+
+        // Methods of a subclass close over an extra scope that binds `super`.
+        let mut method_environment = Rc::clone(&self.environment);
+        if let Some(ref superclass) = superclass_value {
+            let mut environment = Environment::new_with_enclosing(Some(Rc::clone(&self.environment)));
+            environment.define("super".to_string(), Value::Class(Rc::clone(superclass)));
+            method_environment = Rc::new(RefCell::new(environment));
         }
-    }
 
-    fn sum(&self, left: Option<Value>, right: Option<Value>) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (Some(value_left), Some(value_right)) => {
-                match (value_left, value_right) {
-                    (Value::Literal(literal_left), Value::Literal(literal_right)) => {
-                        return self.sum_aux(literal_left, literal_right);
-                    },
-                    _ => Err("[ERROR] Cannot sum boolean values".to_string())
-                }
-            },
-            _ => Err("[ERROR] Cannot sum nil expressions.".to_string())
+        let mut class_methods: HashMap<String, Rc<Function>> = HashMap::new();
+        for method in methods {
+            if let Stmt::Function(method_name, params, body) = method {
+                let is_initializer = method_name.get_lexeme() == "init";
+                let function = Function::new(method_name.clone(), params.clone(), Rc::clone(body), Rc::clone(&method_environment), is_initializer);
+                class_methods.insert(method_name.get_lexeme(), Rc::new(function));
+            }
         }
-    }
 
-    fn sum_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (LiteralPossibleValues::StringValue(value_left), LiteralPossibleValues::StringValue(value_right)) => {
-                return Ok(Some(Value::Literal(LiteralPossibleValues::StringValue(value_left + value_right.as_str()))))
-            },
-            (LiteralPossibleValues::DoubleValue(value_left), LiteralPossibleValues::DoubleValue(value_right)) => {
-                return Ok(Some(Value::Literal(LiteralPossibleValues::DoubleValue(value_left + value_right))));
-            },
+        let class = Class::new(name.get_lexeme(), superclass_value, class_methods);
+        let result = self.environment.borrow_mut().assign(name.get_lexeme(), Value::Class(Rc::new(class)));
 
-            _ => Err("[ERROR] Cannot sum Strings and numbers".to_string())
-        }
+        result.map_err(|e| Error::new(Some(name), e))
     }
 
-    fn greater(&self, left: Option<Value>, right: Option<Value>) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (Some(value_left), Some(value_right)) => {
-                match (value_left, value_right) {
-                    (Value::Literal(literal_left), Value::Literal(literal_right)) => {
-                        return self.greater_aux(literal_left, literal_right);
-                    },
-                    _ => Err("[ERROR] Cannot compare boolean values with literal values".to_string())
+    fn get_super_value(&mut self, expression: Super) -> Result<Value, Error> {
+        // `this` lives in the scope just inside the one that binds `super`.
+        let distance = self.locals.get(&expression.get_id()).copied().unwrap_or(0);
+        let this_token = Token::new(TokenType::This, "this".to_string(), None, expression.get_keyword().get_span());
+
+        let superclass = self.environment.borrow().get_at(distance, expression.get_keyword());
+        let object = self.environment.borrow().get_at(distance.saturating_sub(1), this_token);
+
+        match (superclass, object) {
+            (Ok(Value::Class(superclass)), Ok(Value::Instance(instance))) => {
+                match superclass.find_method(&expression.get_method().get_lexeme()) {
+                    Some(method) => Ok(Value::Function(Rc::new(method.bind(instance)))),
+                    None => Err(Error::new(Some(expression.get_method()), format!("Undefined property '{}'.", expression.get_method().get_lexeme())).with_span(expression.get_span())),
                 }
             },
-            _ => Err("[ERROR] Cannot compare nil expressions.".to_string())
+            _ => Err(Error::new(Some(expression.get_keyword()), "Can't use 'super' outside of a subclass method.".to_string())),
         }
     }
 
-    fn greater_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (LiteralPossibleValues::DoubleValue(value_left), LiteralPossibleValues::DoubleValue(value_right)) => {
-                return Ok(Some(Value::Boolean(value_left > value_right)))
-            },
-            _ => Err("[ERROR] Cannot compare string values".to_string())
-        }
+    fn get_property_value(&mut self, get: Get) -> Result<Value, Error> {
+        let object = self.get_expression_value(*get.get_object())?;
+        self.property(object, get.get_name()).map_err(|e| e.with_span(get.get_span()))
     }
 
-    fn greater_equal(&self, left: Option<Value>, right: Option<Value>) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (Some(value_left), Some(value_right)) => {
-                match (value_left, value_right) {
-                    (Value::Literal(literal_left), Value::Literal(literal_right)) => {
-                        return self.greater_equal_aux(literal_left, literal_right);
-                    },
-                    _ => Err("[ERROR] Cannot compare boolean values with literal values".to_string())
+    // The field `name` of `object`, or one of its methods bound to it.
+    fn property(&self, object: Value, name: Token) -> Result<Value, Error> {
+        match object {
+            Value::Instance(instance) => {
+                if let Some(value) = instance.borrow().get_field(&name) {
+                    return Ok(value);
+                }
+
+                let class = instance.borrow().get_class();
+                if let Some(method) = class.find_method(&name.get_lexeme()) {
+                    return Ok(Value::Function(Rc::new(method.bind(Rc::clone(&instance)))));
                 }
-            },
-            _ => Err("[ERROR] Cannot compare nil expressions.".to_string())
-        }
-    }
 
-    fn greater_equal_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (LiteralPossibleValues::DoubleValue(value_left), LiteralPossibleValues::DoubleValue(value_right)) => {
-                return Ok(Some(Value::Boolean(value_left >= value_right)))
+                Err(Error::new(Some(name.clone()), format!("Undefined property '{}'.", name.get_lexeme())))
             },
-            _ => Err("[ERROR] Cannot compare string values".to_string())
+            _ => Err(Error::new(Some(name), "Only instances have properties.".to_string())),
         }
     }
 
-    fn less(&self, left: Option<Value>, right: Option<Value>) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (Some(value_left), Some(value_right)) => {
-                match (value_left, value_right) {
-                    (Value::Literal(literal_left), Value::Literal(literal_right)) => {
-                        return self.less_aux(literal_left, literal_right);
-                    },
-                    _ => Err("[ERROR] Cannot compare boolean values with literal values".to_string())
-                }
+    fn get_set_value(&mut self, set: Set) -> Result<Value, Error> {
+        let object = self.get_expression_value(*set.get_object())?;
+
+        match object {
+            Value::Instance(instance) => {
+                let value = self.get_expression_value(*set.get_value())?;
+                instance.borrow_mut().set(&set.get_name(), value.clone());
+
+                Ok(value)
             },
-            _ => Err("[ERROR] Cannot compare nil expressions.".to_string())
+            _ => Err(Error::new(Some(set.get_name()), "Only instances have fields.".to_string()).with_span(set.get_span())),
         }
     }
 
-    fn less_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (LiteralPossibleValues::DoubleValue(value_left), LiteralPossibleValues::DoubleValue(value_right)) => {
-                return Ok(Some(Value::Boolean(value_left < value_right)))
-            },
-            _ => Err("[ERROR] Cannot compare string values".to_string())
-        }
+    fn get_group(&mut self, group: Grouping) -> Result<Value, Error> {
+        return self.get_expression_value(*group.get_expression().clone());
     }
 
-    fn less_equal(&self, left: Option<Value>, right: Option<Value>) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (Some(value_left), Some(value_right)) => {
-                match (value_left, value_right) {
-                    (Value::Literal(literal_left), Value::Literal(literal_right)) => {
-                        return self.less_equal_aux(literal_left, literal_right);
-                    },
-                    _ => Err("[ERROR] Cannot compare boolean values with literal values".to_string())
-                }
+    fn get_unary(&mut self, expression: Unary) -> Result<Value, Error> {
+        let expression_result = self.get_expression_value(*expression.get_expression().clone())?;
+        let operator = expression.get_operator().get_token_type();
+
+        match operator {
+            TokenType::Minus => {
+                operators::negate(expression_result).map_err(|message| Error::new(Some(expression.get_operator()), message).with_span(expression.get_span()))
             },
-            _ => Err("[ERROR] Cannot compare nil expressions.".to_string())
-        }
-    }
 
-    fn less_equal_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<Option<Value>, String> {
-        match (left, right) {
-            (LiteralPossibleValues::DoubleValue(value_left), LiteralPossibleValues::DoubleValue(value_right)) => {
-                return Ok(Some(Value::Boolean(value_left <= value_right)))
+            TokenType::Bang => {
+                Ok(Value::Boolean(!operators::is_truthy(&expression_result)))
             },
-            _ => Err("[ERROR] Cannot compare string values".to_string())
+
+            _ => return Err(Error::new(Some(expression.get_operator()), "The token is unary, but do not have an unary operator!".to_string()))
         }
     }
 
-    // Bang Equal and Equal Equal Operations
+    fn get_logical_value(&mut self, value:Logical) -> Result<Value, Error> {
+        let left = self.get_expression_value(*value.get_left().clone())?;
+        let operator = value.get_operator().get_token_type();
 
-    fn is_equal(&self, left: Option<Value>, right: Option<Value>) -> Result<bool, String> {
-        match (left, right) {
-            (None, None) => Ok(true),
-            (None, Some(_)) => Ok(false),
-            (Some(_), None) => Ok(false),
-            (Some(left_value), Some(right_value)) => {
-                match (left_value, right_value) {
-                    (Value::Boolean(value_l), Value::Boolean(value_r)) => Ok(value_l == value_r),
-                    (Value::Literal(value_l), Value::Literal(value_r)) => self.is_equal_aux(value_l, value_r),
-                    _ => Err("[ERROR] Cannot compare booleans with literals".to_string())
+        match operator {
+            TokenType::Or => {
+                if operators::is_truthy(&left) {
+                    return Ok(left);
                 }
             },
-        }
-    }
-
-    fn is_equal_aux(&self, left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<bool, String> {
-        match (left, right) {
-            (LiteralPossibleValues::DoubleValue(number_left), LiteralPossibleValues::DoubleValue(number_right)) => {
-                Ok(number_left == number_right)
+            TokenType::And => {
+                if !operators::is_truthy(&left) {
+                    return Ok(left);
+                }
             },
-            _ => Err("[ERROR] Cannot compare string values".to_string())
+            _ => return Err(Error::new(Some(value.get_operator()), "The Operator is not a logical operator.".to_string()))
         }
+
+        self.get_expression_value(*value.get_right().clone())
     }
 
-}
\ No newline at end of file
+    fn get_binary_expression_result_value(&mut self, value: Binary) -> Result<Value, Error> {
+        let left = self.get_expression_value(*value.get_left().clone())?;
+        let right = self.get_expression_value(*value.get_right().clone())?;
+        let operator = value.get_operator().get_token_type();
+
+        // One shared error path keeps this frame small; it sits on every recursive call.
+        let result = match operator {
+            TokenType::Minus => operators::subtract(left, right),
+            TokenType::Star => operators::multiply(left, right),
+            TokenType::Slash => operators::divide(left, right),
+            TokenType::Percent => operators::modulo(left, right),
+            TokenType::TildeSlash => operators::floor_divide(left, right),
+            TokenType::StarStar => operators::power(left, right),
+            TokenType::Plus => operators::sum(left, right),
+
+            TokenType::Greater => operators::greater(left, right),
+            TokenType::GreaterEqual => operators::greater_equal(left, right),
+            TokenType::Less => operators::less(left, right),
+            TokenType::LessEqual => operators::less_equal(left, right),
+
+            TokenType::BangEqual => operators::is_equal(left, right).map(|equal| Value::Boolean(!equal)),
+            TokenType::EqualEqual => operators::is_equal(left, right).map(Value::Boolean),
+            _ => Err("Operator does not exist!".to_string()),
+        };
+
+        result.map_err(|message| Error::new(Some(value.get_operator()), message).with_span(value.get_span()))
+    }
+}
@@ -0,0 +1,34 @@
+#![allow(clippy::needless_return)]
+
+pub mod token;
+pub mod token_type;
+pub mod scanner;
+pub mod error_hadling;
+pub mod expr;
+pub mod ast_printer;
+pub mod parser;
+pub mod interpreter;
+pub mod stmt;
+pub mod environment;
+pub mod function;
+pub mod class;
+pub mod value;
+pub mod resolver;
+pub mod operators;
+pub mod chunk;
+pub mod compiler;
+pub mod vm;
+pub mod disassembler;
+pub mod lox;
+pub mod native;
+pub mod math;
+pub mod strings;
+pub mod console;
+pub mod files;
+pub mod lists;
+pub mod map;
+
+pub use lox::{Backend, Lox, LoxError};
+pub use error_hadling::{Diagnostic, Phase, Severity};
+pub use native::NativeFunction;
+pub use value::Value;
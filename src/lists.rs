@@ -0,0 +1,21 @@
+use crate::native::{list_argument, native};
+use crate::value::Value;
+
+// List functions, defined as globals. Lists are shared by reference, so these
+// change the list every variable holding it sees.
+pub fn library() -> Vec<(String, Value)> {
+    vec![
+        native("push", 2, |arguments| {
+            let list = list_argument("push", &arguments, 0)?;
+            list.borrow_mut().push(arguments[1].clone());
+
+            Ok(Value::Nil)
+        }),
+        native("pop", 1, |arguments| {
+            let list = list_argument("pop", &arguments, 0)?;
+            let value = list.borrow_mut().pop();
+
+            value.ok_or_else(|| "pop() cannot remove from an empty list.".to_string())
+        }),
+    ]
+}
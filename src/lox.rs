@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::str;
+
+use crate::compiler::Compiler;
+use crate::disassembler;
+use crate::error_hadling::{has_errors, Diagnostic, Phase};
+use crate::interpreter::Interpreter;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use crate::value::Value;
+use crate::vm::Vm;
+
+// Which engine runs the parsed program.
+pub enum Backend {
+    TreeWalker(Interpreter),
+    Bytecode(Vm),
+}
+
+#[derive(Debug)]
+pub enum LoxError {
+    Io(io::Error),
+    // The source was rejected by the scanner, parser, resolver or bytecode compiler.
+    Compile(Vec<Diagnostic>),
+    // The program started running and raised at least one runtime error.
+    Runtime(Vec<Diagnostic>),
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoxError::Io(error) => write!(f, "{}", error),
+            LoxError::Compile(diagnostics) | LoxError::Runtime(diagnostics) => {
+                let lines: Vec<String> = diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            },
+        }
+    }
+}
+
+impl std::error::Error for LoxError {}
+
+impl From<io::Error> for LoxError {
+    fn from(error: io::Error) -> Self {
+        LoxError::Io(error)
+    }
+}
+
+// An embeddable interpreter. Globals persist between calls to `eval`, the same
+// way they persist between lines typed at the prompt.
+pub struct Lox {
+    backend: Backend,
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lox {
+    pub fn new() -> Self {
+        Self::with_backend(Backend::TreeWalker(Interpreter::new()))
+    }
+
+    pub fn with_backend(backend: Backend) -> Self {
+        Lox { backend }
+    }
+
+    // Exposes a Rust function to scripts as the global `name`.
+    pub fn define_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(Vec<Value>) -> Result<Value, String> + 'static,
+    {
+        match &mut self.backend {
+            Backend::TreeWalker(interpreter) => interpreter.define_native(name, arity, function),
+            Backend::Bytecode(vm) => vm.define_native(name, arity, function),
+        }
+    }
+
+    // Sets how many bytes of native stack the tree-walking interpreter may use for nested
+    // calls before it reports a stack overflow. The default, `DEFAULT_STACK_LIMIT`, suits a
+    // thread with Rust's default stack size; raise it when running on a bigger stack.
+    pub fn set_stack_limit(&mut self, bytes: usize) {
+        if let Backend::TreeWalker(interpreter) = &mut self.backend {
+            interpreter.set_stack_limit(bytes);
+        }
+    }
+
+    // Runs `source` and returns the value of its final statement when that is an
+    // expression statement (`1 + 2;` gives 3), or nil otherwise. Only the
+    // tree-walking interpreter produces values; the other backends always give nil.
+    pub fn eval(&mut self, source: &str) -> Result<Value, LoxError> {
+        let Analysis { mut statements, locals, mut diagnostics } = analyze(source)?;
+
+        let mut value = Value::Nil;
+
+        match &mut self.backend {
+            Backend::TreeWalker(interpreter) => {
+                interpreter.resolve(locals);
+
+                let last = match statements.last() {
+                    Some(Stmt::Expr(_)) => statements.pop(),
+                    _ => None,
+                };
+
+                let completed = interpreter.interpret(statements);
+
+                // The trailing expression is part of the program, so it must not run after an error.
+                if let (true, Some(Stmt::Expr(expr))) = (completed, last) {
+                    value = interpreter.evaluate(expr);
+                }
+
+                diagnostics.extend(interpreter.take_diagnostics());
+            },
+            Backend::Bytecode(vm) => {
+                let mut compiler = Compiler::new();
+                let function = compiler.compile(&statements);
+
+                diagnostics.extend(compiler.get_diagnostics());
+
+                if let Some(function) = function {
+                    vm.interpret(function);
+                    diagnostics.extend(vm.take_diagnostics());
+                }
+            },
+        }
+
+        if diagnostics.iter().any(|diagnostic| diagnostic.is_error() && diagnostic.get_phase() == Phase::Runtime) {
+            return Err(LoxError::Runtime(diagnostics));
+        }
+
+        if has_errors(&diagnostics) {
+            return Err(LoxError::Compile(diagnostics));
+        }
+
+        Ok(value)
+    }
+
+    // Compiles `source` to bytecode without running it and returns the disassembly of the
+    // script and of every function it declares.
+    pub fn disassemble(&self, source: &str) -> Result<String, LoxError> {
+        let Analysis { statements, mut diagnostics, .. } = analyze(source)?;
+
+        let mut compiler = Compiler::new();
+        let function = compiler.compile(&statements);
+
+        diagnostics.extend(compiler.get_diagnostics());
+
+        match function {
+            Some(function) if !has_errors(&diagnostics) => Ok(disassembler::disassemble_chunk(&function.chunk, "script")),
+            _ => Err(LoxError::Compile(diagnostics)),
+        }
+    }
+
+    pub fn run_file(&mut self, path: &str) -> Result<Value, LoxError> {
+        let bytes = fs::read(path)?;
+
+        let content = str::from_utf8(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        self.eval(content)
+    }
+}
+
+// A program that was scanned, parsed and resolved without errors.
+struct Analysis {
+    statements: Vec<Stmt>,
+    locals: HashMap<usize, usize>,
+    // Warnings raised on the way.
+    diagnostics: Vec<Diagnostic>,
+}
+
+// Scans, parses and resolves `source`, failing with every diagnostic if one of them is an error.
+fn analyze(source: &str) -> Result<Analysis, LoxError> {
+    let mut scanner = Scanner::new(source.to_string());
+
+    let tokens = scanner.scan_tokens();
+
+    let mut parser = Parser::new(tokens.to_vec());
+
+    let statements = parser.parser();
+
+    let mut diagnostics = scanner.get_diagnostics();
+    diagnostics.extend(parser.get_diagnostics());
+
+    if has_errors(&diagnostics) {
+        return Err(LoxError::Compile(diagnostics));
+    }
+
+    let mut resolver = Resolver::new();
+    resolver.resolve(&statements);
+
+    diagnostics.extend(resolver.get_diagnostics());
+
+    if has_errors(&diagnostics) {
+        return Err(LoxError::Compile(diagnostics));
+    }
+
+    Ok(Analysis { statements, locals: resolver.get_locals(), diagnostics })
+}
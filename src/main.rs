@@ -1,89 +1,139 @@
-pub mod token;
-pub mod token_type;
-pub mod scanner;
-pub mod error_hadling;
-pub mod expr;
-pub mod ast_printer;
-pub mod parser;
-pub mod interpreter;
-pub mod stmt;
-pub mod environment;
-
 use std::env;
-use std::io;
-use std::io::Write;
 use std::fs;
-use std::str;
-
-// use ast_printer::AstPrinter;
-use error_hadling::HAD_ERROR;
-use interpreter::Interpreter;
-use parser::Parser;
-use scanner::Scanner;
+use std::io;
+use std::io::{IsTerminal, Write};
+use std::thread;
+
+use loxrs::console;
+use loxrs::error_hadling;
+use loxrs::interpreter::Interpreter;
+use loxrs::vm::Vm;
+use loxrs::{Backend, Lox, LoxError};
+
+// Exit codes from sysexits.h.
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_SOFTWARE: i32 = 70;
+const EX_IOERR: i32 = 74;
+
+// The tree-walking interpreter recurses on the native stack for every Lox call, so it
+// runs on a big thread with a matching stack limit to reach the same call depth as the VM.
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+// What is done with the source, chosen with `--dump-bytecode` on the command line.
+#[derive(Clone, Copy)]
+enum Mode {
+    Run,
+    // Print the compiled bytecode instead of running it.
+    DumpBytecode,
+}
 
+// How errors are shown, chosen with `--json-diagnostics` on the command line.
+#[derive(Clone, Copy)]
+enum ErrorFormat {
+    Human,
+    // One JSON object per line instead of the rustc-style text.
+    Json,
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() > 2 {
-        println!("Usage: jlox [script]");
-        std::process::exit(0);
-    }
-    else if args.len() == 2{
-        let _ = run_file(&args[1]);
-    }
-    else {
-        run_prompt();
+    let runner = thread::Builder::new().stack_size(STACK_SIZE).spawn(run);
+
+    match runner.map(|handle| handle.join()) {
+        Ok(Ok(())) => {},
+        Ok(Err(_)) => std::process::exit(EX_SOFTWARE),
+        Err(e) => {
+            eprintln!("Could not start the interpreter: {}", e);
+            std::process::exit(EX_SOFTWARE);
+        },
     }
 }
 
-fn run_file(path: &str) -> io::Result<()> {
-    let bytes = fs::read(path)?;
+fn run() {
+    let mut backend = Backend::TreeWalker(Interpreter::new());
+    let mut mode = Mode::Run;
+    let mut error_format = ErrorFormat::Human;
+    let mut args: Vec<String> = Vec::new();
 
-    let content = str::from_utf8(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-    let mut interpreter: Interpreter = Interpreter::new();
-
-    run(content, &mut interpreter);
-
-    unsafe {
-        if error_hadling::HAD_ERROR || error_hadling::HAD_RUNTIME_ERROR {
-            std::process::exit(0);
+    for arg in env::args().skip(1) {
+        if arg == "--vm" {
+            backend = Backend::Bytecode(Vm::new());
+        }
+        else if arg == "--dump-bytecode" {
+            mode = Mode::DumpBytecode;
+        }
+        else if arg == "--json-diagnostics" {
+            error_format = ErrorFormat::Json;
+        }
+        else {
+            args.push(arg);
         }
     }
 
-    Ok(())
+    let mut lox = Lox::with_backend(backend);
+    lox.set_stack_limit(STACK_SIZE / 2);
+
+    if args.len() > 1 {
+        eprintln!("Usage: jlox [--vm | --dump-bytecode] [--json-diagnostics] [script]");
+        std::process::exit(EX_USAGE);
+    }
+    else if args.len() == 1 {
+        run_file(&mut lox, &args[0], mode, error_format);
+    }
+    else {
+        run_prompt(&mut lox, mode, error_format);
+    }
 }
 
+fn execute(lox: &mut Lox, source: &str, mode: Mode) -> Result<(), LoxError> {
+    match mode {
+        Mode::Run => lox.eval(source).map(|_| ()),
+        Mode::DumpBytecode => lox.disassemble(source).map(|disassembly| print!("{}", disassembly)),
+    }
+}
 
-fn run_prompt() {
-    let stdin = io::stdin();
-    let mut buffer = String::new();
+fn run_file(lox: &mut Lox, path: &str, mode: Mode, error_format: ErrorFormat) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Could not read '{}': {}", path, e);
+            std::process::exit(EX_IOERR);
+        },
+    };
+
+    if let Err(e) = execute(lox, &source, mode) {
+        report(&e, path, &source, error_format);
+
+        let code = match e {
+            LoxError::Io(_) => EX_IOERR,
+            LoxError::Compile(_) => EX_DATAERR,
+            LoxError::Runtime(_) => EX_SOFTWARE,
+        };
+        std::process::exit(code);
+    }
+}
 
-    let mut interpreter: Interpreter = Interpreter::new();
 
+fn run_prompt(lox: &mut Lox, mode: Mode, error_format: ErrorFormat) {
     loop {
         print!("> ");
         io::stdout().flush().unwrap();
 
-        buffer.clear();
-
-        match stdin.read_line(&mut buffer) {
-            Ok(bytes) => {
-                if bytes == 0 {
-                    println!("[Exiting]");
-                    break;
-                }
-
-                let input = buffer.trim();
+        // Lines the script reads with input() or readLine() are taken from the
+        // same stdin, so they are never seen here as code.
+        match console::read_line() {
+            Ok(None) => {
+                println!("[Exiting]");
+                break;
+            },
+            Ok(Some(line)) => {
+                let input = line.trim();
                 if input.is_empty() {
                     continue;
                 }
 
-                run(input, &mut interpreter);
-
-                unsafe {
-                    error_hadling::HAD_ERROR = false;
+                if let Err(e) = execute(lox, input, mode) {
+                    report(&e, "<stdin>", input, error_format);
                 }
             },
             Err(error) => {
@@ -94,18 +144,27 @@ fn run_prompt() {
     }
 }
 
-fn run(input: &str, interpreter: &mut Interpreter) {
-    let mut scanner = Scanner::new(input.to_string());
-
-    let tokens = scanner.scan_tokens();
-
-    let mut parser = Parser::new(tokens.to_vec());
-
-    let statements = parser.parser();
-
-    if unsafe { HAD_ERROR } {
-        return;
+fn report(error: &LoxError, file_name: &str, source: &str, error_format: ErrorFormat) {
+    let diagnostics = match error {
+        LoxError::Io(e) => {
+            eprintln!("{}", e);
+            return;
+        },
+        LoxError::Compile(diagnostics) | LoxError::Runtime(diagnostics) => diagnostics,
+    };
+
+    match error_format {
+        ErrorFormat::Human => {
+            let colour = io::stderr().is_terminal();
+
+            for diagnostic in diagnostics {
+                eprint!("{}", error_hadling::render(diagnostic, file_name, source, colour));
+            }
+        },
+        ErrorFormat::Json => {
+            for diagnostic in diagnostics {
+                eprintln!("{}", error_hadling::render_json(diagnostic, file_name));
+            }
+        },
     }
-
-    interpreter.interpret(statements);
-}
\ No newline at end of file
+}
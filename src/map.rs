@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::native::{map_argument, native};
+use crate::token::LiteralPossibleValues;
+use crate::value::Value;
+
+// The hashable form of a map key. Numbers are stored by their bits, with -0 folded
+// into 0, so that keys are equal exactly when `operators::is_equal` says they are.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Key {
+    Nil,
+    Boolean(bool),
+    Number(u64),
+    String(String),
+}
+
+impl Key {
+    fn from_value(value: &Value) -> Result<Key, String> {
+        match value {
+            Value::Nil => Ok(Key::Nil),
+            Value::Boolean(boolean) => Ok(Key::Boolean(*boolean)),
+            Value::Literal(LiteralPossibleValues::DoubleValue(number)) => {
+                if number.is_nan() {
+                    return Err("Map keys cannot be NaN.".to_string());
+                }
+
+                Ok(Key::Number(if *number == 0.0 { 0.0_f64.to_bits() } else { number.to_bits() }))
+            },
+            Value::Literal(LiteralPossibleValues::StringValue(string)) => Ok(Key::String(string.clone())),
+            _ => Err("Map keys must be strings, numbers, booleans or nil.".to_string()),
+        }
+    }
+}
+
+// A hash map that remembers insertion order, so that printing a map and
+// `keys()` give the same result on every run.
+#[derive(Debug, Default)]
+pub struct Map {
+    entries: Vec<(Value, Value)>,
+    positions: HashMap<Key, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, key: &Value) -> Result<Option<Value>, String> {
+        let position = self.positions.get(&Key::from_value(key)?);
+
+        Ok(position.map(|position| self.entries[*position].1.clone()))
+    }
+
+    pub fn insert(&mut self, key: Value, value: Value) -> Result<(), String> {
+        match self.positions.get(&Key::from_value(&key)?) {
+            Some(position) => self.entries[*position].1 = value,
+            None => {
+                self.positions.insert(Key::from_value(&key)?, self.entries.len());
+                self.entries.push((key, value));
+            },
+        }
+
+        Ok(())
+    }
+
+    pub fn contains(&self, key: &Value) -> Result<bool, String> {
+        Ok(self.positions.contains_key(&Key::from_value(key)?))
+    }
+
+    // Removes `key`, giving back its value if it was present.
+    pub fn remove(&mut self, key: &Value) -> Result<Option<Value>, String> {
+        let position = match self.positions.remove(&Key::from_value(key)?) {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+
+        let (_, value) = self.entries.remove(position);
+        for later in self.positions.values_mut() {
+            if *later > position {
+                *later -= 1;
+            }
+        }
+
+        Ok(Some(value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn keys(&self) -> Vec<Value> {
+        self.entries.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    pub fn values(&self) -> Vec<Value> {
+        self.entries.iter().map(|(_, value)| value.clone()).collect()
+    }
+
+    // Writes `{key: value, ...}`; see `Value::write` for `open`.
+    pub fn write(&self, f: &mut fmt::Formatter, open: &mut Vec<*const ()>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (index, (key, value)) in self.entries.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            key.write(f, open)?;
+            write!(f, ": ")?;
+            value.write(f, open)?;
+        }
+
+        write!(f, "}}")
+    }
+}
+
+
+// Map functions, defined as globals.
+pub fn library() -> Vec<(String, Value)> {
+    vec![
+        native("keys", 1, |arguments| {
+            Ok(Value::list(map_argument("keys", &arguments, 0)?.borrow().keys()))
+        }),
+        native("values", 1, |arguments| {
+            Ok(Value::list(map_argument("values", &arguments, 0)?.borrow().values()))
+        }),
+        native("has", 2, |arguments| {
+            let map = map_argument("has", &arguments, 0)?;
+            let contains = map.borrow().contains(&arguments[1])?;
+
+            Ok(Value::Boolean(contains))
+        }),
+        native("remove", 2, |arguments| {
+            let map = map_argument("remove", &arguments, 0)?;
+            let removed = map.borrow_mut().remove(&arguments[1])?;
+
+            Ok(removed.unwrap_or(Value::Nil))
+        }),
+    ]
+}
@@ -0,0 +1,61 @@
+use std::f64::consts;
+
+use crate::native::{native, number_argument};
+use crate::value::Value;
+
+// Numeric functions and constants, defined as globals.
+pub fn library() -> Vec<(String, Value)> {
+    vec![
+        ("PI".to_string(), Value::number(consts::PI)),
+        ("E".to_string(), Value::number(consts::E)),
+
+        unary("abs", f64::abs),
+        unary("floor", f64::floor),
+        unary("ceil", f64::ceil),
+        unary("round", f64::round),
+        unary("sin", f64::sin),
+        unary("cos", f64::cos),
+        unary("tan", f64::tan),
+        unary("atan", f64::atan),
+        unary("exp", f64::exp),
+
+        native("sqrt", 1, |arguments| {
+            let number = number_argument("sqrt", &arguments, 0)?;
+            if number < 0.0 {
+                return Err("sqrt() of a negative number.".to_string());
+            }
+
+            Ok(Value::number(number.sqrt()))
+        }),
+        native("log", 1, |arguments| {
+            let number = number_argument("log", &arguments, 0)?;
+            if number <= 0.0 {
+                return Err("log() of a number that is not positive.".to_string());
+            }
+
+            Ok(Value::number(number.ln()))
+        }),
+
+        binary("pow", f64::powf),
+        binary("atan2", f64::atan2),
+        binary("min", f64::min),
+        binary("max", f64::max),
+    ]
+}
+
+fn unary(name: &'static str, function: fn(f64) -> f64) -> (String, Value) {
+    native(name, 1, move |arguments| {
+        let number = number_argument(name, &arguments, 0)?;
+
+        Ok(Value::number(function(number)))
+    })
+}
+
+fn binary(name: &'static str, function: fn(f64, f64) -> f64) -> (String, Value) {
+    native(name, 2, move |arguments| {
+        let left = number_argument(name, &arguments, 0)?;
+        let right = number_argument(name, &arguments, 1)?;
+
+        Ok(Value::number(function(left, right)))
+    })
+}
@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::console;
+use crate::files;
+use crate::lists;
+use crate::map::{self, Map};
+use crate::math;
+use crate::strings;
+use crate::token::LiteralPossibleValues;
+use crate::value::Value;
+
+// A function implemented in Rust. It receives exactly `arity` arguments and
+// returns either a value or the message of a runtime error.
+pub type NativeFn = dyn Fn(Vec<Value>) -> Result<Value, String>;
+
+#[derive(Clone)]
+pub struct NativeFunction {
+    name: String,
+    arity: usize,
+    function: Rc<NativeFn>,
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+impl NativeFunction {
+    pub fn new<F>(name: &str, arity: usize, function: F) -> Self
+    where
+        F: Fn(Vec<Value>) -> Result<Value, String> + 'static,
+    {
+        NativeFunction {
+            name: name.to_string(),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn call(&self, arguments: Vec<Value>) -> Result<Value, String> {
+        (self.function)(arguments)
+    }
+}
+
+// The globals every interpreter starts with: native functions and a few constants.
+pub fn standard_library() -> Vec<(String, Value)> {
+    let mut globals = vec![
+        native("clock", 0, clock),
+    ];
+
+    globals.extend(math::library());
+    globals.extend(strings::library());
+    globals.extend(lists::library());
+    globals.extend(map::library());
+    globals.extend(console::library());
+    globals.extend(files::library());
+
+    globals
+}
+
+// A native function paired with the global name it is defined under.
+pub fn native<F>(name: &str, arity: usize, function: F) -> (String, Value)
+where
+    F: Fn(Vec<Value>) -> Result<Value, String> + 'static,
+{
+    (name.to_string(), Value::NativeFunction(Rc::new(NativeFunction::new(name, arity, function))))
+}
+
+// The argument at `index` as a number, or an error naming the function that needed it.
+pub fn number_argument(function: &str, arguments: &[Value], index: usize) -> Result<f64, String> {
+    match arguments.get(index) {
+        Some(Value::Literal(LiteralPossibleValues::DoubleValue(number))) => Ok(*number),
+        _ => Err(format!("{}() expects a number as argument {}.", function, index + 1)),
+    }
+}
+
+// The argument at `index` as a whole, non-negative number, for use as an index or count.
+pub fn index_argument(function: &str, arguments: &[Value], index: usize) -> Result<usize, String> {
+    let number = number_argument(function, arguments, index)?;
+
+    if number < 0.0 || number.fract() != 0.0 {
+        return Err(format!("{}() expects a non-negative whole number as argument {}.", function, index + 1));
+    }
+
+    Ok(number as usize)
+}
+
+pub fn string_argument(function: &str, arguments: &[Value], index: usize) -> Result<String, String> {
+    match arguments.get(index) {
+        Some(Value::Literal(LiteralPossibleValues::StringValue(string))) => Ok(string.clone()),
+        _ => Err(format!("{}() expects a string as argument {}.", function, index + 1)),
+    }
+}
+
+pub fn list_argument(function: &str, arguments: &[Value], index: usize) -> Result<Rc<RefCell<Vec<Value>>>, String> {
+    match arguments.get(index) {
+        Some(Value::List(list)) => Ok(Rc::clone(list)),
+        _ => Err(format!("{}() expects a list as argument {}.", function, index + 1)),
+    }
+}
+
+pub fn map_argument(function: &str, arguments: &[Value], index: usize) -> Result<Rc<RefCell<Map>>, String> {
+    match arguments.get(index) {
+        Some(Value::Map(map)) => Ok(Rc::clone(map)),
+        _ => Err(format!("{}() expects a map as argument {}.", function, index + 1)),
+    }
+}
+
+// Seconds since the Unix epoch, with sub-second precision.
+fn clock(_arguments: Vec<Value>) -> Result<Value, String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?;
+
+    Ok(Value::number(now.as_secs_f64()))
+}
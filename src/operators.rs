@@ -0,0 +1,222 @@
+use std::rc::Rc;
+
+use crate::token::LiteralPossibleValues;
+use crate::value::Value;
+
+// Semantics of Lox's operators, shared by the tree-walking interpreter and the bytecode VM
+// so both backends agree on results and error messages.
+
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Nil => false,
+        Value::Boolean(value) => *value,
+        _ => true,
+    }
+}
+
+pub fn negate(value: Value) -> Result<Value, String> {
+    match value {
+        Value::Literal(LiteralPossibleValues::DoubleValue(val)) => Ok(Value::Literal(LiteralPossibleValues::DoubleValue(-val))),
+        _ => Err(format!("Cannot negate {} values", value.type_name())),
+    }
+}
+
+pub fn subtract(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "subtract")?;
+
+    Ok(Value::number(number_left - number_right))
+}
+
+pub fn multiply(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "multiply")?;
+
+    Ok(Value::number(number_left * number_right))
+}
+
+pub fn divide(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "divide")?;
+
+    if number_right == 0.0 {
+        return Err("Cannot divide by zero".to_string());
+    }
+
+    Ok(Value::number(number_left / number_right))
+}
+
+// `%`, floored like `~/`: the result has the sign of the divisor, so that
+// `a == (a ~/ b) * b + a % b`.
+pub fn modulo(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "take the remainder of")?;
+
+    if number_right == 0.0 {
+        return Err("Cannot divide by zero".to_string());
+    }
+
+    let remainder = number_left % number_right;
+    if remainder != 0.0 && (remainder < 0.0) != (number_right < 0.0) {
+        return Ok(Value::number(remainder + number_right));
+    }
+
+    Ok(Value::number(remainder))
+}
+
+// `~/`, division rounded towards negative infinity.
+pub fn floor_divide(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "divide")?;
+
+    if number_right == 0.0 {
+        return Err("Cannot divide by zero".to_string());
+    }
+
+    Ok(Value::number((number_left / number_right).floor()))
+}
+
+pub fn power(left: Value, right: Value) -> Result<Value, String> {
+    let (base, exponent) = number_operands(left, right, "exponentiate")?;
+
+    // The same as dividing by zero.
+    if base == 0.0 && exponent < 0.0 {
+        return Err("Cannot divide by zero".to_string());
+    }
+
+    Ok(Value::number(base.powf(exponent)))
+}
+
+// Both operands as numbers, or an error naming the operation and the operand types.
+fn number_operands(left: Value, right: Value, verb: &str) -> Result<(f64, f64), String> {
+    match (left, right) {
+        (Value::Literal(LiteralPossibleValues::DoubleValue(number_left)), Value::Literal(LiteralPossibleValues::DoubleValue(number_right))) => {
+            Ok((number_left, number_right))
+        },
+        (left, right) => Err(operand_error(verb, &left, &right)),
+    }
+}
+
+fn operand_error(verb: &str, left: &Value, right: &Value) -> String {
+    format!("Cannot {} {} and {}", verb, left.type_name(), right.type_name())
+}
+
+pub fn sum(left: Value, right: Value) -> Result<Value, String> {
+    match (left, right) {
+        (Value::Literal(LiteralPossibleValues::StringValue(value_left)), Value::Literal(LiteralPossibleValues::StringValue(value_right))) => {
+            Ok(Value::string(value_left + value_right.as_str()))
+        },
+        (Value::Literal(LiteralPossibleValues::DoubleValue(value_left)), Value::Literal(LiteralPossibleValues::DoubleValue(value_right))) => {
+            Ok(Value::number(value_left + value_right))
+        },
+        (left, right) => Err(operand_error("sum", &left, &right)),
+    }
+}
+
+pub fn greater(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "compare")?;
+
+    Ok(Value::Boolean(number_left > number_right))
+}
+
+pub fn greater_equal(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "compare")?;
+
+    Ok(Value::Boolean(number_left >= number_right))
+}
+
+pub fn less(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "compare")?;
+
+    Ok(Value::Boolean(number_left < number_right))
+}
+
+pub fn less_equal(left: Value, right: Value) -> Result<Value, String> {
+    let (number_left, number_right) = number_operands(left, right, "compare")?;
+
+    Ok(Value::Boolean(number_left <= number_right))
+}
+
+// Bang Equal and Equal Equal Operations
+
+pub fn is_equal(left: Value, right: Value) -> Result<bool, String> {
+    match (left, right) {
+        (Value::Nil, Value::Nil) => Ok(true),
+        (Value::Nil, _) => Ok(false),
+        (_, Value::Nil) => Ok(false),
+        (Value::Boolean(value_l), Value::Boolean(value_r)) => Ok(value_l == value_r),
+        (Value::Literal(value_l), Value::Literal(value_r)) => is_equal_aux(value_l, value_r),
+        (Value::Function(value_l), Value::Function(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::Class(value_l), Value::Class(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::Instance(value_l), Value::Instance(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::Closure(value_l), Value::Closure(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::BytecodeClass(value_l), Value::BytecodeClass(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::BytecodeInstance(value_l), Value::BytecodeInstance(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::BoundMethod(value_l), Value::BoundMethod(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::NativeFunction(value_l), Value::NativeFunction(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::List(value_l), Value::List(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        (Value::Map(value_l), Value::Map(value_r)) => Ok(Rc::ptr_eq(&value_l, &value_r)),
+        // Values of different types are never equal.
+        _ => Ok(false)
+    }
+}
+
+fn is_equal_aux(left: LiteralPossibleValues, right: LiteralPossibleValues) -> Result<bool, String> {
+    match (left, right) {
+        (LiteralPossibleValues::DoubleValue(number_left), LiteralPossibleValues::DoubleValue(number_right)) => {
+            Ok(number_left == number_right)
+        },
+        (LiteralPossibleValues::StringValue(string_left), LiteralPossibleValues::StringValue(string_right)) => {
+            Ok(string_left == string_right)
+        },
+        _ => Ok(false)
+    }
+}
+
+// `object[index]`.
+pub fn get_index(object: Value, index: Value) -> Result<Value, String> {
+    match object {
+        Value::List(list) => {
+            let list = list.borrow();
+            let position = list_position(&index, list.len())?;
+
+            Ok(list[position].clone())
+        },
+        Value::Map(map) => match map.borrow().get(&index)? {
+            Some(value) => Ok(value),
+            None => Err(format!("Key '{}' is not in the map.", index)),
+        },
+        _ => Err("Only lists and maps can be indexed.".to_string()),
+    }
+}
+
+// `object[index] = value`, giving back the assigned value.
+pub fn set_index(object: Value, index: Value, value: Value) -> Result<Value, String> {
+    match object {
+        Value::List(list) => {
+            let mut list = list.borrow_mut();
+            let position = list_position(&index, list.len())?;
+            list[position] = value.clone();
+
+            Ok(value)
+        },
+        Value::Map(map) => {
+            map.borrow_mut().insert(index, value.clone())?;
+
+            Ok(value)
+        },
+        _ => Err("Only lists and maps can be indexed.".to_string()),
+    }
+}
+
+fn list_position(index: &Value, length: usize) -> Result<usize, String> {
+    let number = match index {
+        Value::Literal(LiteralPossibleValues::DoubleValue(number)) => *number,
+        _ => return Err("List index must be a number.".to_string()),
+    };
+
+    if number.fract() != 0.0 {
+        return Err(format!("List index must be a whole number, not {}.", number));
+    }
+
+    if number < 0.0 || number >= length as f64 {
+        return Err(format!("Index {} is out of bounds for a list of length {}.", number, length));
+    }
+
+    Ok(number as usize)
+}
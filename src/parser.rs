@@ -1,12 +1,13 @@
-use crate::token::Token;
+use crate::token::{LiteralPossibleValues, Token};
 use crate::token_type::TokenType;
 use crate::expr::*;
 use crate::stmt::*;
-use crate::error_hadling::parser_error;
+use crate::error_hadling::{Diagnostic, Phase};
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: i32,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Parser {
@@ -14,7 +15,8 @@ impl Parser {
         let current = 0;
         Parser {
             tokens,
-            current
+            current,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -22,40 +24,98 @@ impl Parser {
         let mut statements: Vec<Stmt> = Vec::new();
 
         while !self.is_at_end() {
-            let stmt = self.declaration();
-            match stmt {
-                Ok(value) => {
-                    if let Some(val) = value {
-                        statements.push(val);
-                    }
-                },
-                Err(e) => println!("{}", e),
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
             }
         }
 
         statements
     }
 
-    fn declaration(&mut self) -> Result<Option<Stmt>, String> {
-        let result = self.declaration_aux();
+    pub fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.clone()
+    }
 
-        match result {
-            Ok(value) => Ok(value),
+    // Errors have already been recorded as diagnostics, so a failed declaration only
+    // needs to resynchronize and give nothing back.
+    fn declaration(&mut self) -> Option<Stmt> {
+        match self.declaration_aux() {
+            Ok(value) => value,
             Err(_) => {
                 self.syncronize();
-                return Ok(None);
+                None
             },
         }
     }
 
     fn declaration_aux(&mut self) -> Result<Option<Stmt>, String> {
+        if self.match_signal(&vec![TokenType::Class]) {
+            return self.class_declaration();
+        }
+        if self.match_signal(&vec![TokenType::Fun]) {
+            return self.function("function");
+        }
         if self.match_signal(&vec![TokenType::Var]) {
             return self.var_declaration();
         }
         return self.statement();
     }
 
+    fn class_declaration(&mut self) -> Result<Option<Stmt>, String> {
+        let name = self.consume(TokenType::Identifier, "Expect class name.".to_string())?;
+
+        let mut superclass: Option<Expr> = None;
+        if self.match_signal(&vec![TokenType::Less]) {
+            let superclass_name = self.consume(TokenType::Identifier, "Expect superclass name.".to_string())?;
+            superclass = Some(Expr::Variable(Some(Variable::new(superclass_name))));
+        }
+
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.".to_string())?;
+
+        let mut methods: Vec<Stmt> = Vec::new();
+
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if let Some(method) = self.function("method")? {
+                methods.push(method);
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.".to_string())?;
+
+        Ok(Some(Stmt::Class(name, superclass, methods)))
+    }
+
+    fn function(&mut self, kind: &str) -> Result<Option<Stmt>, String> {
+        let name = self.consume(TokenType::Identifier, format!("Expect {} name.", kind))?;
+
+        self.consume(TokenType::LeftParen, format!("Expect '(' after {} name.", kind))?;
+
+        let mut parameters: Vec<Token> = Vec::new();
+
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if parameters.len() >= 255 {
+                    self.error(self.peek(), "Can't have more than 255 parameters.".to_string());
+                }
+
+                parameters.push(self.consume(TokenType::Identifier, "Expect parameter name.".to_string())?);
+
+                if !self.match_signal(&vec![TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.".to_string())?;
+        self.consume(TokenType::LeftBrace, format!("Expect '{{' before {} body.", kind))?;
+
+        let body = self.block()?;
+
+        Ok(Some(Stmt::Function(name, parameters, body.into())))
+    }
+
     fn var_declaration(&mut self) -> Result<Option<Stmt>, String> {
+        let keyword = self.previous();
         let name = self.consume(TokenType::Identifier, "Expect variable name.".to_string())?;
 
         let mut initializer: Option<Expr> = None;
@@ -67,7 +127,7 @@ impl Parser {
 
         let _ = self.consume(TokenType::Semicolon, "Expect ';' after variable declaration.".to_string());
 
-        Ok(Some(Stmt::Var(name, initializer)))
+        Ok(Some(Stmt::Var(keyword, name, initializer)))
     }
 
     fn statement(&mut self) -> Result<Option<Stmt>, String> {
@@ -90,7 +150,18 @@ impl Parser {
         if self.match_signal(_types) {
             return self.print_statement();
         }
-        if self.match_signal(&vec![TokenType::LeftBrace]) {
+
+        let _types = &vec![TokenType::Return];
+        if self.match_signal(_types) {
+            return self.return_statement();
+        }
+
+        if self.match_signal(&vec![TokenType::Break, TokenType::Continue]) {
+            return self.loop_jump_statement();
+        }
+
+        if self.check(TokenType::LeftBrace) && !self.starts_map_literal() {
+            self.advance();
             return Ok(Some(Stmt::Block(self.block()?)));
         }
 
@@ -98,6 +169,7 @@ impl Parser {
     }
 
     fn for_statement(&mut self) -> Result<Option<Stmt>, String> {
+        let keyword = self.previous();
         let _ = self.consume(TokenType::LeftParen, "Expect '(' after 'for'.".to_string());
 
         let initialize: Option<Stmt>;
@@ -129,15 +201,11 @@ impl Parser {
 
         let mut body: Option<Stmt> = self.statement()?;
 
-        if let Some(increment_value) = increment {
-            body = Some(Stmt::Block(vec![body.unwrap(), Stmt::Expr(increment_value)]));
+        if condition.is_none() {
+            condition = Some(Expr::Literal(Some(Literal::new(Token::new(TokenType::True, "true".to_string(), None, self.previous().get_span())))));
         }
 
-        if let None = condition {
-            condition = Some(Expr::Literal(Some(Literal::new(Token::new(TokenType::True, "true".to_string(), None, self.previous().get_line())))));
-        }
-
-        body = Some(Stmt::While(condition.unwrap(), Box::new(body.unwrap())));
+        body = Some(Stmt::While(keyword, condition.unwrap(), Box::new(body.unwrap()), increment));
 
         if let Some(initialize_value) = initialize {
             body = Some(Stmt::Block(vec![initialize_value, body.unwrap()]));
@@ -147,6 +215,7 @@ impl Parser {
     }
 
     fn if_statement(&mut self) -> Result<Option<Stmt>, String> {
+        let keyword = self.previous();
         let _ = self.consume(TokenType::LeftParen, "Expect '(' after 'if'.".to_string());
         let condition = self.expression()?;
         let _ = self.consume(TokenType::RightParen, "Expect ')' after if condition.".to_string());
@@ -165,31 +234,57 @@ impl Parser {
             }
         }
 
-        Ok(Some(Stmt::If(condition, Box::new(then_statement), else_branch)))
+        Ok(Some(Stmt::If(keyword, condition, Box::new(then_statement), else_branch)))
 
     }
 
     fn while_statement(&mut self) -> Result<Option<Stmt>, String> {
+        let keyword = self.previous();
         let _ = self.consume(TokenType::LeftParen, "Expect '(' after 'if'.".to_string());
         let condition = self.expression()?;
         let _ = self.consume(TokenType::RightParen, "Expect ')' after if condition.".to_string());
 
         let while_statement = self.statement()?.unwrap();
 
-        Ok(Some(Stmt::While(condition, Box::new(while_statement))))
+        Ok(Some(Stmt::While(keyword, condition, Box::new(while_statement), None)))
     }
 
     fn print_statement(&mut self) -> Result<Option<Stmt>, String> {
+        let keyword = self.previous();
         let value = self.expression();
         match value {
             Ok(val) => {
                 let _ = self.consume(TokenType::Semicolon, "Expect ';' after value.".to_string());
-                return Ok(Some(Stmt::Print(val)));
+                return Ok(Some(Stmt::Print(keyword, val)));
             },
             Err(e) => Err(e),
         }
     }
 
+    fn return_statement(&mut self) -> Result<Option<Stmt>, String> {
+        let keyword = self.previous();
+        let mut value: Option<Expr> = None;
+
+        if !self.check(TokenType::Semicolon) {
+            value = Some(self.expression()?);
+        }
+
+        let _ = self.consume(TokenType::Semicolon, "Expect ';' after return value.".to_string());
+
+        Ok(Some(Stmt::Return(keyword, value)))
+    }
+
+    // `break;` or `continue;`. The resolver checks that it is inside a loop.
+    fn loop_jump_statement(&mut self) -> Result<Option<Stmt>, String> {
+        let keyword = self.previous();
+        self.consume(TokenType::Semicolon, format!("Expect ';' after '{}'.", keyword.get_lexeme()))?;
+
+        match keyword.get_token_type() {
+            TokenType::Break => Ok(Some(Stmt::Break(keyword))),
+            _ => Ok(Some(Stmt::Continue(keyword))),
+        }
+    }
+
     fn expression_statement(&mut self) -> Result<Option<Stmt>, String> {
         let value = self.expression();
         match value {
@@ -205,7 +300,10 @@ impl Parser {
         let mut statements: Vec<Stmt> = Vec::new();
 
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            statements.push(self.declaration()?.unwrap());
+            // A declaration that failed to parse has already been reported.
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
         }
 
         let _ = self.consume(TokenType::RightBrace, "Expect '}' after block.".to_string());
@@ -228,18 +326,58 @@ impl Parser {
                 Expr::Variable(variable_value) => {
                     match variable_value {
                         Some(name) => return Ok(Expr::Assign(Some(Assign::new(name.get_value(), Box::new(value))))),
-                        None => return Err("[ERROR] Empty variable.".to_string()),
+                        None => return Err("Empty variable.".to_string()),
+                    }
+                },
+                Expr::Get(get_value) => {
+                    match get_value {
+                        Some(get) => return Ok(Expr::Set(Some(Set::new(get.get_object(), get.get_name(), Box::new(value))))),
+                        None => return Err("Empty property access.".to_string()),
+                    }
+                },
+                Expr::Index(index_value) => {
+                    match index_value {
+                        Some(index) => return Ok(Expr::SetIndex(Some(SetIndex::new(index.get_object(), index.get_bracket(), index.get_index(), Box::new(value))))),
+                        None => return Err("Empty index access.".to_string()),
                     }
                 },
                 _ => {},
             }
 
-            parser_error(equals, "Invalid assignment target.".to_string());
+            self.error(equals, "Invalid assignment target.".to_string());
+        }
+
+        let compound = &vec![TokenType::PlusEqual, TokenType::MinusEqual, TokenType::StarEqual, TokenType::SlashEqual];
+        if self.match_signal(compound) {
+            let operator = self.previous();
+            let value = self.assignment()?;
+
+            return Ok(self.update(expression, operator, value, false));
         }
 
         Ok(expression)
     }
 
+    // An `Update` of `target`, or `target` itself after reporting it when it is not
+    // something that can be assigned to.
+    fn update(&mut self, target: Expr, operator: Token, value: Expr, postfix: bool) -> Expr {
+        match target {
+            Expr::Variable(Some(_)) | Expr::Get(Some(_)) | Expr::Index(Some(_)) => {
+                Expr::Update(Some(Update::new(Box::new(target), operator, Box::new(value), postfix)))
+            },
+            _ => {
+                self.error(operator, "Invalid assignment target.".to_string());
+                target
+            },
+        }
+    }
+
+    // The literal 1 that `++` and `--` add or subtract, placed at the operator.
+    fn one(operator: &Token) -> Expr {
+        let token = Token::new(TokenType::Number, "1".to_string(), Some(LiteralPossibleValues::DoubleValue(1.0)), operator.get_span());
+        Expr::Literal(Some(Literal::new(token)))
+    }
+
     fn or(&mut self) -> Result<Expr, String> {
         let mut expression = self.and()?;
         let _type = vec![TokenType::Or];
@@ -331,7 +469,7 @@ impl Parser {
     fn factor(&mut self) -> Result<Expr, String> {
         let mut expr = self.unary()?;
 
-        let types = &vec![TokenType::Slash, TokenType::Star];
+        let types = &vec![TokenType::Slash, TokenType::Star, TokenType::Percent, TokenType::TildeSlash];
 
         while self.match_signal(types) {
             let operator = self.previous();
@@ -349,7 +487,7 @@ impl Parser {
     fn unary(&mut self) -> Result<Expr, String> {
         let types = &vec![TokenType::Bang, TokenType::Minus];
 
-        while self.match_signal(types) {
+        if self.match_signal(types) {
             let operator = self.previous();
             let right = self.unary()?;
 
@@ -358,7 +496,86 @@ impl Parser {
             return Ok(Expr::Unary(Some(Unary::new(operator, right_pointer))));
         }
 
-        return self.primary();
+        if self.match_signal(&vec![TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous();
+            let target = self.unary()?;
+
+            return Ok(self.update(target, operator.clone(), Self::one(&operator), false));
+        }
+
+        return self.power();
+    }
+
+    // `**` binds tighter than unary minus and groups to the right, so `-2 ** 2` is -4
+    // and `2 ** 3 ** 2` is 512.
+    fn power(&mut self) -> Result<Expr, String> {
+        let expr = self.postfix()?;
+
+        if self.match_signal(&vec![TokenType::StarStar]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+
+            return Ok(Expr::Binary(Some(Binary::new(Box::new(expr), operator, Box::new(right)))));
+        }
+
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let expr = self.call()?;
+
+        if self.match_signal(&vec![TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous();
+            return Ok(self.update(expr, operator.clone(), Self::one(&operator), true));
+        }
+
+        Ok(expr)
+    }
+
+    fn call(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_signal(&vec![TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            }
+            else if self.match_signal(&vec![TokenType::Dot]) {
+                let name = self.consume(TokenType::Identifier, "Expect property name after '.'.".to_string())?;
+                expr = Expr::Get(Some(Get::new(Box::new(expr), name)));
+            }
+            else if self.match_signal(&vec![TokenType::LeftBracket]) {
+                let index = self.expression()?;
+                let bracket = self.consume(TokenType::RightBracket, "Expect ']' after index.".to_string())?;
+                expr = Expr::Index(Some(Index::new(Box::new(expr), bracket, Box::new(index))));
+            }
+            else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, String> {
+        let mut arguments: Vec<Expr> = Vec::new();
+
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if arguments.len() >= 255 {
+                    self.error(self.peek(), "Can't have more than 255 arguments.".to_string());
+                }
+
+                arguments.push(self.expression()?);
+
+                if !self.match_signal(&vec![TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.".to_string())?;
+
+        Ok(Expr::Call(Some(Call::new(Box::new(callee), paren, arguments))))
     }
 
     fn primary(&mut self) -> Result<Expr, String> {
@@ -384,12 +601,70 @@ impl Parser {
 
         // Grouping case
         if self.match_signal(&vec![TokenType::LeftParen]) {
+            let left_paren = self.previous();
             let expression = self.expression()?;
             let _ = self.consume(TokenType::RightParen, "Expect ')' after expression.".to_string());
 
+            let span = left_paren.get_span().to(self.previous().get_span());
             let expression = Box::new(expression);
 
-            return Ok(Expr::Grouping(Some(Grouping::new(expression))));
+            return Ok(Expr::Grouping(Some(Grouping::new(expression, span))));
+        }
+
+        // List case
+        if self.match_signal(&vec![TokenType::LeftBracket]) {
+            let left_bracket = self.previous();
+            let mut elements: Vec<Expr> = Vec::new();
+
+            if !self.check(TokenType::RightBracket) {
+                loop {
+                    elements.push(self.expression()?);
+
+                    if !self.match_signal(&vec![TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+
+            let right_bracket = self.consume(TokenType::RightBracket, "Expect ']' after list elements.".to_string())?;
+            let span = left_bracket.get_span().to(right_bracket.get_span());
+
+            return Ok(Expr::List(Some(List::new(elements, span))));
+        }
+
+        if self.match_signal(&vec![TokenType::LeftBrace]) {
+            let left_brace = self.previous();
+            let mut entries: Vec<(Expr, Expr)> = Vec::new();
+
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    let key = self.expression()?;
+                    self.consume(TokenType::Colon, "Expect ':' after map key.".to_string())?;
+                    let value = self.expression()?;
+                    entries.push((key, value));
+
+                    if !self.match_signal(&vec![TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+
+            let right_brace = self.consume(TokenType::RightBrace, "Expect '}' after map entries.".to_string())?;
+            let span = left_brace.get_span().to(right_brace.get_span());
+
+            return Ok(Expr::Map(Some(Map::new(left_brace, entries, span))));
+        }
+
+        if self.match_signal(&vec![TokenType::Super]) {
+            let keyword = self.previous();
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.".to_string())?;
+            let method = self.consume(TokenType::Identifier, "Expect superclass method name.".to_string())?;
+
+            return Ok(Expr::Super(Some(Super::new(keyword, method))));
+        }
+
+        if self.match_signal(&vec![TokenType::This]) {
+            return Ok(Expr::This(Some(This::new(self.previous()))));
         }
 
         if self.match_signal(&vec![TokenType::Identifier]) {
@@ -436,6 +711,17 @@ impl Parser {
         return self.previous();
     }
 
+    // A `{` at the start of a statement opens a block, unless it is followed by a
+    // single-token key and a `:`, as in `{"a": 1}["a"];`.
+    fn starts_map_literal(&self) -> bool {
+        let current = self.current as usize;
+
+        match (self.tokens.get(current + 1), self.tokens.get(current + 2)) {
+            (Some(key), Some(colon)) => key.get_token_type() != TokenType::RightBrace && colon.get_token_type() == TokenType::Colon,
+            _ => false,
+        }
+    }
+
     fn is_at_end(&self) -> bool {
         self.peek().get_token_type() == TokenType::Eof
     }
@@ -448,8 +734,8 @@ impl Parser {
         self.tokens.get((self.current as usize) - 1).unwrap().clone()
     }
 
-    fn error(&self, token: Token, message: String) -> String {
-        parser_error(token, message);
+    fn error(&mut self, token: Token, message: String) -> String {
+        self.diagnostics.push(Diagnostic::error_at(Phase::Parse, &token, message));
 
         "Parser ERROR".to_string()
     }
@@ -471,6 +757,8 @@ impl Parser {
                 TokenType::While => return,
                 TokenType::Print => return,
                 TokenType::Return => return,
+                TokenType::Break => return,
+                TokenType::Continue => return,
                 _ => {}
             }
 
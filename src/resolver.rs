@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use crate::error_hadling::{Diagnostic, Phase};
+use crate::expr::*;
+use crate::stmt::Stmt;
+use crate::token::Token;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+// Walks the tree once before execution, recording how many environments
+// separate each variable use from the scope that declares it.
+pub struct Resolver {
+    locals: HashMap<usize, usize>,
+    scopes: Vec<HashMap<String, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    // Loops enclosing the current statement within the current function.
+    loop_depth: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Resolver {
+            locals: HashMap::new(),
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            loop_depth: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    // The depth of every local variable reference, keyed by expression id.
+    pub fn get_locals(&self) -> HashMap<usize, usize> {
+        self.locals.clone()
+    }
+
+    pub fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.clone()
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+    }
+
+    fn resolve_statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                self.resolve(statements);
+                self.end_scope();
+            },
+            Stmt::Break(keyword) | Stmt::Continue(keyword) => {
+                if self.loop_depth == 0 {
+                    self.error(keyword, format!("Can't use '{}' outside of a loop.", keyword.get_lexeme()));
+                }
+            },
+            Stmt::Class(name, superclass, methods) => {
+                self.resolve_class(name, superclass, methods);
+            },
+            Stmt::Expr(expr) => self.resolve_expression(expr),
+            Stmt::Function(name, params, body) => {
+                self.declare(name);
+                self.define(name);
+
+                self.resolve_function(params, body, FunctionType::Function);
+            },
+            Stmt::If(_, condition, then_statement, else_statement) => {
+                self.resolve_expression(condition);
+                self.resolve_statement(then_statement);
+                if let Some(else_stmt) = else_statement {
+                    self.resolve_statement(else_stmt);
+                }
+            },
+            Stmt::Print(_, expr) => self.resolve_expression(expr),
+            Stmt::Return(keyword, value) => {
+                if self.current_function == FunctionType::None {
+                    self.error(keyword, "Can't return from top-level code.".to_string());
+                }
+
+                if let Some(expr) = value {
+                    if self.current_function == FunctionType::Initializer {
+                        self.error(keyword, "Can't return a value from an initializer.".to_string());
+                    }
+
+                    self.resolve_expression(expr);
+                }
+            },
+            Stmt::Var(_, name, initializer) => {
+                self.declare(name);
+                if let Some(expr) = initializer {
+                    self.resolve_expression(expr);
+                }
+                self.define(name);
+            },
+            Stmt::While(_, condition, body, increment) => {
+                self.resolve_expression(condition);
+
+                self.loop_depth += 1;
+                self.resolve_statement(body);
+                self.loop_depth -= 1;
+
+                if let Some(expr) = increment {
+                    self.resolve_expression(expr);
+                }
+            },
+        }
+    }
+
+    fn resolve_class(&mut self, name: &Token, superclass: &Option<Expr>, methods: &[Stmt]) {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+
+        self.declare(name);
+        self.define(name);
+
+        if let Some(superclass_expr) = superclass {
+            if let Expr::Variable(Some(variable)) = superclass_expr {
+                if variable.get_value().get_lexeme() == name.get_lexeme() {
+                    self.error(&variable.get_value(), "A class can't inherit from itself.".to_string());
+                }
+            }
+
+            self.current_class = ClassType::Subclass;
+            self.resolve_expression(superclass_expr);
+
+            self.begin_scope();
+            self.define_name("super");
+        }
+
+        self.begin_scope();
+        self.define_name("this");
+
+        for method in methods {
+            if let Stmt::Function(method_name, params, body) = method {
+                let declaration = if method_name.get_lexeme() == "init" {
+                    FunctionType::Initializer
+                } else {
+                    FunctionType::Method
+                };
+
+                self.resolve_function(params, body, declaration);
+            }
+        }
+
+        self.end_scope();
+
+        if superclass.is_some() {
+            self.end_scope();
+        }
+
+        self.current_class = enclosing_class;
+    }
+
+    fn resolve_function(&mut self, params: &[Token], body: &[Stmt], function_type: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = function_type;
+        let enclosing_loop_depth = self.loop_depth;
+        self.loop_depth = 0;
+
+        self.begin_scope();
+        for param in params {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve(body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
+    }
+
+    fn resolve_expression(&mut self, expression: &Expr) {
+        match expression {
+            Expr::Assign(Some(assign)) => {
+                self.resolve_expression(&assign.get_expression());
+                self.resolve_local(assign.get_id(), &assign.get_value());
+            },
+            Expr::Binary(Some(binary)) => {
+                self.resolve_expression(&binary.get_left());
+                self.resolve_expression(&binary.get_right());
+            },
+            Expr::Call(Some(call)) => {
+                self.resolve_expression(&call.get_callee());
+                for argument in call.get_arguments() {
+                    self.resolve_expression(&argument);
+                }
+            },
+            Expr::Get(Some(get)) => {
+                self.resolve_expression(&get.get_object());
+            },
+            Expr::Index(Some(index)) => {
+                self.resolve_expression(&index.get_object());
+                self.resolve_expression(&index.get_index());
+            },
+            Expr::List(Some(list)) => {
+                for element in list.get_elements() {
+                    self.resolve_expression(&element);
+                }
+            },
+            Expr::Map(Some(map)) => {
+                for (key, value) in map.get_entries() {
+                    self.resolve_expression(&key);
+                    self.resolve_expression(&value);
+                }
+            },
+            Expr::SetIndex(Some(set_index)) => {
+                self.resolve_expression(&set_index.get_object());
+                self.resolve_expression(&set_index.get_index());
+                self.resolve_expression(&set_index.get_value());
+            },
+            Expr::Update(Some(update)) => {
+                self.resolve_expression(&update.get_value());
+                self.resolve_expression(&update.get_target());
+            },
+            Expr::Grouping(Some(grouping)) => {
+                self.resolve_expression(&grouping.get_expression());
+            },
+            Expr::Literal(_) => {},
+            Expr::Logical(Some(logical)) => {
+                self.resolve_expression(&logical.get_left());
+                self.resolve_expression(&logical.get_right());
+            },
+            Expr::Set(Some(set)) => {
+                self.resolve_expression(&set.get_value());
+                self.resolve_expression(&set.get_object());
+            },
+            Expr::Super(Some(super_expr)) => {
+                match self.current_class {
+                    ClassType::None => self.error(&super_expr.get_keyword(), "Can't use 'super' outside of a class.".to_string()),
+                    ClassType::Class => self.error(&super_expr.get_keyword(), "Can't use 'super' in a class with no superclass.".to_string()),
+                    ClassType::Subclass => {},
+                }
+
+                self.resolve_local(super_expr.get_id(), &super_expr.get_keyword());
+            },
+            Expr::This(Some(this)) => {
+                if self.current_class == ClassType::None {
+                    self.error(&this.get_keyword(), "Can't use 'this' outside of a class.".to_string());
+                    return;
+                }
+
+                self.resolve_local(this.get_id(), &this.get_keyword());
+            },
+            Expr::Unary(Some(unary)) => {
+                self.resolve_expression(&unary.get_expression());
+            },
+            Expr::Variable(Some(variable)) => {
+                let name = variable.get_value();
+
+                let in_own_initializer = match self.scopes.last() {
+                    Some(scope) => scope.get(&name.get_lexeme()) == Some(&false),
+                    None => false,
+                };
+
+                if in_own_initializer {
+                    self.error(&name, "Can't read local variable in its own initializer.".to_string());
+                }
+
+                self.resolve_local(variable.get_id(), &name);
+            },
+            _ => {},
+        }
+    }
+
+    fn resolve_local(&mut self, id: usize, name: &Token) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.get_lexeme()) {
+                self.locals.insert(id, depth);
+                return;
+            }
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let already_declared = match self.scopes.last() {
+            Some(scope) => scope.contains_key(&name.get_lexeme()),
+            None => return,
+        };
+
+        if already_declared {
+            self.error(name, "Already a variable with this name in this scope.".to_string());
+        }
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.get_lexeme(), false);
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.get_lexeme(), true);
+        }
+    }
+
+    fn define_name(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), true);
+        }
+    }
+
+    fn error(&mut self, token: &Token, message: String) {
+        self.diagnostics.push(Diagnostic::error_at(Phase::Resolve, token, message));
+    }
+}
@@ -1,6 +1,6 @@
-use crate::token::{LiteralPossibleValues, Token};
+use crate::token::{LiteralPossibleValues, Span, Token};
 use crate::token_type::TokenType;
-use crate::error_hadling::*;
+use crate::error_hadling::{Diagnostic, Phase};
 use std::collections::HashMap;
 
 pub struct Scanner {
@@ -9,7 +9,12 @@ pub struct Scanner {
     start: i32,
     current: i32,
     line: i32,
+    // Offset where the current line begins, for computing columns.
+    line_start: i32,
+    start_line: i32,
+    start_column: usize,
     keywords: HashMap<String, TokenType>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Scanner {
@@ -17,7 +22,9 @@ impl Scanner {
         let mut keywords = HashMap::new();
 
         keywords.insert("and".to_string(), TokenType::And);
+        keywords.insert("break".to_string(), TokenType::Break);
         keywords.insert("class".to_string(), TokenType::Class);
+        keywords.insert("continue".to_string(), TokenType::Continue);
         keywords.insert("else".to_string(), TokenType::Else);
         keywords.insert("false".to_string(), TokenType::False);
         keywords.insert("for".to_string(), TokenType::For);
@@ -39,23 +46,56 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
-            keywords: keywords
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            keywords,
+            diagnostics: Vec::new(),
         }
     }
 
     pub fn scan_tokens(&mut self) -> &Vec<Token> {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column_at(self.start);
             self.scan_token();
         }
 
-        let token: Token = Token::new(TokenType::Eof, String::new(), None, self.line);
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column_at(self.start);
+
+        let token: Token = Token::new(TokenType::Eof, String::new(), None, self.current_span());
 
         self.tokens.push(token);
 
         &self.tokens
     }
 
+    pub fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.clone()
+    }
+
+    fn error(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic::error(Phase::Scan, self.line, message).with_span(self.current_span()));
+    }
+
+    // The span of the lexeme being scanned, from `start` up to `current`.
+    fn current_span(&self) -> Span {
+        Span::new(self.start as usize, self.current as usize, self.start_line, self.start_column)
+    }
+
+    // The 1-based column, counted in characters, of the byte `offset` on the current line.
+    fn column_at(&self, offset: i32) -> usize {
+        self.source[self.line_start as usize..offset as usize].chars().count() + 1
+    }
+
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
     fn scan_token(&mut self) {
         let character = self.advance();
 
@@ -64,12 +104,29 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
+            ':' => self.add_token(TokenType::Colon),
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
+            '-' => {
+                if self.match_next('-') {self.add_token(TokenType::MinusMinus);}
+                else if self.match_next('=') {self.add_token(TokenType::MinusEqual);}
+                else {self.add_token(TokenType::Minus);}
+            },
+            '+' => {
+                if self.match_next('+') {self.add_token(TokenType::PlusPlus);}
+                else if self.match_next('=') {self.add_token(TokenType::PlusEqual);}
+                else {self.add_token(TokenType::Plus);}
+            },
             ';' => self.add_token(TokenType::Semicolon),
-            '*' => self.add_token(TokenType::Star),
+            '%' => self.add_token(TokenType::Percent),
+            '~' if self.match_next('/') => self.add_token(TokenType::TildeSlash),
+            '*' => {
+                if self.match_next('*') {self.add_token(TokenType::StarStar);}
+                else if self.match_next('=') {self.add_token(TokenType::StarEqual);}
+                else {self.add_token(TokenType::Star);}
+            },
 
             '!' => {if self.match_next('=') {self.add_token(TokenType::BangEqual);} else {self.add_token(TokenType::Bang);}},
             '=' => {if self.match_next('=') {self.add_token(TokenType::EqualEqual);} else {self.add_token(TokenType::Equal);}},
@@ -77,14 +134,19 @@ impl Scanner {
             '>' => {if self.match_next('=') {self.add_token(TokenType::GreaterEqual);} else {self.add_token(TokenType::Greater);}},
 
             '/' => {
-                if self.match_next('/') {
+                if self.match_next('=') {
+                    self.add_token(TokenType::SlashEqual);
+                }
+                else if self.match_next('/') {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
                 }
                 else if self.match_next('*') {
                     while (self.peek() != '*' || self.peek_next() != '/') && !self.is_at_end() {
-                        self.advance();
+                        if self.advance() == '\n' {
+                            self.new_line();
+                        }
                     }
                     self.advance();
                     self.advance();
@@ -100,7 +162,7 @@ impl Scanner {
             '\t' => {},
             '\r' => {},
 
-            '\n' => self.line += 1,
+            '\n' => self.new_line(),
 
             _ => {
                 if self.is_digit(character) {
@@ -110,7 +172,7 @@ impl Scanner {
                     self.identifier();
                 }
                 else {
-                    error(self.line, format!("Unexpected character: {}", character).as_str());
+                    self.error(format!("Unexpected character: {}", character));
                 }
             },
         }
@@ -157,15 +219,14 @@ impl Scanner {
 
     fn string(&mut self) {
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+            if self.advance() == '\n' {
+                self.new_line();
             }
-
-            self.advance();
         }
 
         if self.is_at_end() {
-            error(self.line, "Unterminated string.");
+            self.error("Unterminated string.".to_string());
+            return;
         }
 
         self.advance(); // for the last "
@@ -181,14 +242,14 @@ impl Scanner {
             return false;
         }
 
-        let character = self.source.chars().nth(self.current as usize);
+        let character = self.source[self.current as usize..].chars().next();
 
         if let Some(value) = character {
             if value != expected {
                 return false;
             }
             else {
-                self.current += 1;
+                self.current += value.len_utf8() as i32;
                 return true;
             }
         } else {
@@ -201,7 +262,7 @@ impl Scanner {
             return '\0';
         }
 
-        let character = self.source.chars().nth(self.current as usize);
+        let character = self.source[self.current as usize..].chars().next();
 
         if let Some(value) = character {
             return value;
@@ -215,7 +276,7 @@ impl Scanner {
             return '\0';
         }
         else {
-            let character = self.source.chars().nth((self.current + 1) as usize);
+            let character = self.source[self.current as usize..].chars().nth(1);
 
             if let Some(value) = character {
                 return value;
@@ -252,12 +313,14 @@ impl Scanner {
     }
 
     fn advance(&mut self) -> char {
-        let character: Option<char> = self.source.chars().nth(self.current as usize);
-        self.current += 1;
-
-        match character {
-            Some(a) => a,
-            None => '\0'
+        // `current` is a byte offset, so step over the whole UTF-8 sequence. At the end
+        // there is nothing to step over and `current` stays put.
+        match self.source[self.current as usize..].chars().next() {
+            Some(character) => {
+                self.current += character.len_utf8() as i32;
+                character
+            },
+            None => '\0',
         }
     }
 
@@ -269,7 +332,7 @@ impl Scanner {
         let source_string: String = self.source.clone();
         let sub_string: String = source_string.get(self.start as usize..self.current as usize).unwrap_or_default().to_string();
 
-        let token: Token = Token::new(token_type, sub_string, literal, self.line);
+        let token: Token = Token::new(token_type, sub_string, literal, self.current_span());
         self.tokens.push(token);
     }
 
@@ -1,12 +1,66 @@
+use std::rc::Rc;
+
 use crate::expr::Expr;
-use crate::token::Token;
+use crate::token::{Span, Token};
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum Stmt {
+    Class(Token, Option<Expr>, Vec<Stmt>),
     Expr(Expr),
-    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    // The body is shared with every function value created from the declaration.
+    Function(Token, Vec<Token>, Rc<[Stmt]>),
+    If(Token, Expr, Box<Stmt>, Option<Box<Stmt>>),
     Block(Vec<Stmt>),
-    Print(Expr),
-    Var(Token, Option<Expr>),
-    While(Expr, Box<Stmt>)
+    Break(Token),
+    Continue(Token),
+    Print(Token, Expr),
+    Return(Token, Option<Expr>),
+    // The `var` keyword, the name and the initializer.
+    Var(Token, Token, Option<Expr>),
+    // Keyword, condition, body and, for a desugared `for`, the increment. The increment
+    // runs after the body even when it ends with `continue`.
+    While(Token, Expr, Box<Stmt>, Option<Expr>)
+}
+
+impl Stmt {
+    // The source range covered by the statement's own tokens and sub-nodes, from its
+    // leading keyword. Declarations start at their name, since methods have no keyword,
+    // and punctuation that is not kept in the tree (braces, semicolons) falls outside it.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Stmt::Class(name, _, methods) => span_with_statements(name.get_span(), methods),
+            Stmt::Expr(expr) => expr.get_span(),
+            Stmt::Print(keyword, expr) => keyword.get_span().to(expr.get_span()),
+            Stmt::Function(name, _, body) => span_with_statements(name.get_span(), body),
+            Stmt::If(keyword, _, then_statement, else_statement) => {
+                let span = keyword.get_span().to(then_statement.get_span());
+
+                match else_statement {
+                    Some(else_stmt) => span.to(else_stmt.get_span()),
+                    None => span,
+                }
+            },
+            Stmt::Block(statements) => match statements.first() {
+                Some(first) => span_with_statements(first.get_span(), statements),
+                None => Span::default(),
+            },
+            Stmt::Return(keyword, value) => match value {
+                Some(expr) => keyword.get_span().to(expr.get_span()),
+                None => keyword.get_span(),
+            },
+            Stmt::Var(keyword, name, value) => match value {
+                Some(expr) => keyword.get_span().to(expr.get_span()),
+                None => keyword.get_span().to(name.get_span()),
+            },
+            Stmt::Break(keyword) | Stmt::Continue(keyword) => keyword.get_span(),
+            Stmt::While(keyword, _, body, _) => keyword.get_span().to(body.get_span()),
+        }
+    }
+}
+
+fn span_with_statements(span: Span, statements: &[Stmt]) -> Span {
+    match statements.last() {
+        Some(last) => span.to(last.get_span()),
+        None => span,
+    }
 }
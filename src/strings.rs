@@ -0,0 +1,99 @@
+use crate::native::{index_argument, native, string_argument};
+use crate::token::LiteralPossibleValues;
+use crate::value::Value;
+
+// String functions, defined as globals. Lengths and indices count characters, not bytes.
+pub fn library() -> Vec<(String, Value)> {
+    vec![
+        native("len", 1, |arguments| {
+            match &arguments[0] {
+                Value::Literal(LiteralPossibleValues::StringValue(string)) => Ok(Value::number(string.chars().count() as f64)),
+                Value::List(list) => Ok(Value::number(list.borrow().len() as f64)),
+                Value::Map(map) => Ok(Value::number(map.borrow().len() as f64)),
+                _ => Err("len() expects a string, list or map as argument 1.".to_string()),
+            }
+        }),
+        native("substr", 3, |arguments| {
+            let string = string_argument("substr", &arguments, 0)?;
+            let start = index_argument("substr", &arguments, 1)?;
+            let end = index_argument("substr", &arguments, 2)?;
+            let length = string.chars().count();
+
+            if start > end || end > length {
+                return Err(format!("substr() range {}..{} is out of bounds for a string of length {}.", start, end, length));
+            }
+
+            Ok(Value::string(string.chars().skip(start).take(end - start).collect()))
+        }),
+        native("indexOf", 2, |arguments| {
+            let string = string_argument("indexOf", &arguments, 0)?;
+            let needle = string_argument("indexOf", &arguments, 1)?;
+
+            let index = match string.find(&needle) {
+                Some(byte_index) => string[..byte_index].chars().count() as f64,
+                None => -1.0,
+            };
+
+            Ok(Value::number(index))
+        }),
+        native("contains", 2, |arguments| {
+            let string = string_argument("contains", &arguments, 0)?;
+            let needle = string_argument("contains", &arguments, 1)?;
+
+            Ok(Value::Boolean(string.contains(&needle)))
+        }),
+        native("startsWith", 2, |arguments| {
+            let string = string_argument("startsWith", &arguments, 0)?;
+            let prefix = string_argument("startsWith", &arguments, 1)?;
+
+            Ok(Value::Boolean(string.starts_with(&prefix)))
+        }),
+        native("endsWith", 2, |arguments| {
+            let string = string_argument("endsWith", &arguments, 0)?;
+            let suffix = string_argument("endsWith", &arguments, 1)?;
+
+            Ok(Value::Boolean(string.ends_with(&suffix)))
+        }),
+        native("upper", 1, |arguments| {
+            Ok(Value::string(string_argument("upper", &arguments, 0)?.to_uppercase()))
+        }),
+        native("lower", 1, |arguments| {
+            Ok(Value::string(string_argument("lower", &arguments, 0)?.to_lowercase()))
+        }),
+        native("trim", 1, |arguments| {
+            Ok(Value::string(string_argument("trim", &arguments, 0)?.trim().to_string()))
+        }),
+        native("replace", 3, |arguments| {
+            let string = string_argument("replace", &arguments, 0)?;
+            let from = string_argument("replace", &arguments, 1)?;
+            let to = string_argument("replace", &arguments, 2)?;
+
+            if from.is_empty() {
+                return Err("replace() cannot replace an empty string.".to_string());
+            }
+
+            Ok(Value::string(string.replace(&from, &to)))
+        }),
+        native("split", 2, |arguments| {
+            let string = string_argument("split", &arguments, 0)?;
+            let separator = string_argument("split", &arguments, 1)?;
+
+            if separator.is_empty() {
+                return Ok(Value::list(string.chars().map(|c| Value::string(c.to_string())).collect()));
+            }
+
+            Ok(Value::list(string.split(&separator).map(|part| Value::string(part.to_string())).collect()))
+        }),
+        native("toNumber", 1, |arguments| {
+            let string = string_argument("toNumber", &arguments, 0)?;
+
+            match string.trim().parse::<f64>() {
+                Ok(number) => Ok(Value::number(number)),
+                Err(_) => Ok(Value::Nil),
+            }
+        }),
+        native("toString", 1, |arguments| {
+            Ok(Value::string(arguments[0].to_string()))
+        }),
+    ]
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::token_type::TokenType;
 
 #[derive(Debug, Clone)]
@@ -6,21 +8,78 @@ pub enum LiteralPossibleValues {
     DoubleValue(f64),
 }
 
+// A range of the source: byte offsets `start..end`, plus the line and the
+// 1-based column where it begins.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    start: usize,
+    end: usize,
+    line: i32,
+    column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: i32, column: usize) -> Self {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    pub fn get_start(&self) -> usize {
+        self.start
+    }
+
+    pub fn get_end(&self) -> usize {
+        self.end
+    }
+
+    pub fn get_line(&self) -> i32 {
+        self.line
+    }
+
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    // The smallest span covering both `self` and `other`. A default span means
+    // "unknown" and leaves the other one unchanged.
+    pub fn to(&self, other: Span) -> Span {
+        if other == Span::default() {
+            return *self;
+        }
+        if *self == Span::default() {
+            return other;
+        }
+
+        let (first, last) = if self.start <= other.start { (*self, other) } else { (other, *self) };
+
+        Span {
+            start: first.start,
+            end: first.end.max(last.end),
+            line: first.line,
+            column: first.column,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Token {
     token_type: TokenType,
     lexeme: String,
-    line: i32,
+    span: Span,
     literal: Option<LiteralPossibleValues>,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, literal: Option<LiteralPossibleValues>, line: i32) -> Self {
+    pub fn new(token_type: TokenType, lexeme: String, literal: Option<LiteralPossibleValues>, span: Span) -> Self {
         Token {
             token_type,
             lexeme,
             literal,
-            line,
+            span,
         }
     }
 
@@ -33,22 +92,37 @@ impl Token {
     }
 
     pub fn get_line(&self) -> i32 {
-        self.line
+        self.span.get_line()
+    }
+
+    pub fn get_column(&self) -> usize {
+        self.span.get_column()
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_literal(&self) -> Option<LiteralPossibleValues> {
         self.literal.clone()
     }
 
-    pub fn to_string(&self) -> String {
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let type_ = match &self.token_type {
             TokenType::LeftParen => "LeftParen",
             TokenType::RightParen => "RightParen",
             TokenType::LeftBrace => "LeftBrace",
             TokenType::RightBrace => "RightBrace",
+            TokenType::LeftBracket => "LeftBracket",
+            TokenType::RightBracket => "RightBracket",
+            TokenType::Colon => "Colon",
             TokenType::Comma => "Comma",
             TokenType::Dot => "Dot",
             TokenType::Minus => "Minus",
+            TokenType::Percent => "Percent",
             TokenType::Plus => "Plus",
             TokenType::Semicolon => "Semicolon",
             TokenType::Slash => "Slash",
@@ -62,6 +136,14 @@ impl Token {
             TokenType::GreaterEqual => "GreaterEqual",
             TokenType::Less => "Less",
             TokenType::LessEqual => "LessEqual",
+            TokenType::TildeSlash => "TildeSlash",
+            TokenType::StarStar => "StarStar",
+            TokenType::PlusEqual => "PlusEqual",
+            TokenType::MinusEqual => "MinusEqual",
+            TokenType::StarEqual => "StarEqual",
+            TokenType::SlashEqual => "SlashEqual",
+            TokenType::PlusPlus => "PlusPlus",
+            TokenType::MinusMinus => "MinusMinus",
 
             TokenType::Identifier => "Identifier",
             TokenType::String => "String",
@@ -91,9 +173,12 @@ impl Token {
 
             TokenType::Print => "Print",
 
+            TokenType::Break => "Break",
+            TokenType::Continue => "Continue",
+
             TokenType::Eof => "Eof",
         };
 
-        format!("{} {} {:?}", type_, self.lexeme, self.literal)
+        write!(f, "{} {} {:?}", type_, self.lexeme, self.literal)
     }
 }
\ No newline at end of file
@@ -2,14 +2,17 @@
 #[derive(Clone, PartialEq, Debug)]
 pub enum TokenType {
     // Single Characters
-    LeftParen, RightParen, LeftBrace, RightBrace,
-    Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+    LeftParen, RightParen, LeftBrace, RightBrace, LeftBracket, RightBracket,
+    Colon, Comma, Dot, Minus, Percent, Plus, Semicolon, Slash, Star,
 
     // One or two characters
     Bang, BangEqual,
     Equal, EqualEqual,
     Greater, GreaterEqual,
     Less, LessEqual,
+    TildeSlash, StarStar,
+    PlusEqual, MinusEqual, StarEqual, SlashEqual,
+    PlusPlus, MinusMinus,
 
     // Literals
     Identifier, String, Number,
@@ -23,6 +26,7 @@ pub enum TokenType {
     Nil,
     Class, Super, This, Fun,
     Return, Print,
+    Break, Continue,
 
     Eof,
 }
\ No newline at end of file
@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::class::{Class, Instance};
+use crate::function::Function;
+use crate::map::Map;
+use crate::native::NativeFunction;
+use crate::token::LiteralPossibleValues;
+use crate::vm::{BoundMethod, BytecodeClass, BytecodeInstance, Closure};
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Nil,
+    Boolean(bool),
+    Literal(LiteralPossibleValues),
+    Function(Rc<Function>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+    Closure(Rc<Closure>),
+    BytecodeClass(Rc<BytecodeClass>),
+    BytecodeInstance(Rc<RefCell<BytecodeInstance>>),
+    BoundMethod(Rc<BoundMethod>),
+    NativeFunction(Rc<NativeFunction>),
+    List(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<Map>>)
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, &mut Vec::new())
+    }
+}
+
+impl Value {
+    // Writes the value the way `print` shows it. `open` holds the lists and maps already being
+    // written around this value, so a collection that contains itself shows as `[...]` or `{...}`.
+    pub fn write(&self, f: &mut fmt::Formatter, open: &mut Vec<*const ()>) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "Nil"),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Literal(LiteralPossibleValues::StringValue(value)) => write!(f, "{}", value),
+            Value::Literal(LiteralPossibleValues::DoubleValue(value)) => write!(f, "{}", value),
+            Value::Function(function) => write!(f, "<fn {}>", function.get_name().get_lexeme()),
+            Value::Class(class) => write!(f, "{}", class.get_name()),
+            Value::Instance(instance) => write!(f, "{} instance", instance.borrow().get_class().get_name()),
+            Value::Closure(closure) => write!(f, "<fn {}>", closure.get_name()),
+            Value::BytecodeClass(class) => write!(f, "{}", class.get_name()),
+            Value::BytecodeInstance(instance) => write!(f, "{} instance", instance.borrow().get_class().get_name()),
+            Value::BoundMethod(method) => write!(f, "<fn {}>", method.get_name()),
+            Value::NativeFunction(native) => write!(f, "<native fn {}>", native.get_name()),
+            Value::List(list) => {
+                let pointer = Rc::as_ptr(list) as *const ();
+                if open.contains(&pointer) {
+                    return write!(f, "[...]");
+                }
+
+                open.push(pointer);
+                write!(f, "[")?;
+                for (index, element) in list.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.write(f, open)?;
+                }
+                open.pop();
+
+                write!(f, "]")
+            },
+            Value::Map(map) => {
+                let pointer = Rc::as_ptr(map) as *const ();
+                if open.contains(&pointer) {
+                    return write!(f, "{{...}}");
+                }
+
+                open.push(pointer);
+                map.borrow().write(f, open)?;
+                open.pop();
+
+                Ok(())
+            },
+        }
+    }
+
+    // The name of the value's type, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Boolean(_) => "boolean",
+            Value::Literal(LiteralPossibleValues::DoubleValue(_)) => "number",
+            Value::Literal(LiteralPossibleValues::StringValue(_)) => "string",
+            Value::Function(_) | Value::Closure(_) | Value::BoundMethod(_) | Value::NativeFunction(_) => "function",
+            Value::Class(_) | Value::BytecodeClass(_) => "class",
+            Value::Instance(_) | Value::BytecodeInstance(_) => "instance",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    pub fn number(value: f64) -> Self {
+        Value::Literal(LiteralPossibleValues::DoubleValue(value))
+    }
+
+    pub fn string(value: String) -> Self {
+        Value::Literal(LiteralPossibleValues::StringValue(value))
+    }
+
+    pub fn list(elements: Vec<Value>) -> Self {
+        Value::List(Rc::new(RefCell::new(elements)))
+    }
+
+    pub fn map(map: Map) -> Self {
+        Value::Map(Rc::new(RefCell::new(map)))
+    }
+}
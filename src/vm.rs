@@ -0,0 +1,652 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::chunk::{BytecodeFunction, Constant, OpCode};
+use crate::error_hadling::{Diagnostic, Phase, StackFrame};
+use crate::map::Map;
+use crate::native::{self, NativeFunction};
+use crate::operators;
+use crate::token::{LiteralPossibleValues, Span};
+use crate::value::Value;
+
+const FRAMES_MAX: usize = 1024;
+
+#[derive(Debug)]
+pub enum Upvalue {
+    // The captured variable still lives on the stack, at this slot.
+    Open(usize),
+    Closed(Value),
+}
+
+pub struct Closure {
+    function: Rc<BytecodeFunction>,
+    upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+// Upvalues may hold the closure itself, so Debug only shows the name.
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}>", self.function.name)
+    }
+}
+
+impl Closure {
+    pub fn get_name(&self) -> String {
+        self.function.name.clone()
+    }
+}
+
+// A class created by the VM; `Inherit` and `Method` fill in its methods after it is made.
+pub struct BytecodeClass {
+    name: String,
+    methods: RefCell<HashMap<String, Rc<Closure>>>,
+}
+
+impl fmt::Debug for BytecodeClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl BytecodeClass {
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn find_method(&self, name: &str) -> Option<Rc<Closure>> {
+        self.methods.borrow().get(name).cloned()
+    }
+}
+
+pub struct BytecodeInstance {
+    class: Rc<BytecodeClass>,
+    fields: HashMap<String, Value>,
+}
+
+// Fields may point back at the instance itself, so Debug only shows the class.
+impl fmt::Debug for BytecodeInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
+
+impl BytecodeInstance {
+    pub fn get_class(&self) -> Rc<BytecodeClass> {
+        Rc::clone(&self.class)
+    }
+}
+
+// A method read off an instance, remembering the instance to call it on.
+#[derive(Debug)]
+pub struct BoundMethod {
+    receiver: Value,
+    method: Rc<Closure>,
+}
+
+impl BoundMethod {
+    pub fn get_name(&self) -> String {
+        self.method.get_name()
+    }
+}
+
+struct CallFrame {
+    closure: Rc<Closure>,
+    ip: usize,
+    // Index of the stack slot holding the callee; its locals follow.
+    slots: usize,
+}
+
+pub struct Vm {
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    globals: HashMap<String, Value>,
+    open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        let mut globals = HashMap::new();
+
+        for (name, value) in native::standard_library() {
+            globals.insert(name, value);
+        }
+
+        Vm {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals,
+            open_upvalues: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    // Exposes a Rust function to scripts as the global `name`.
+    pub fn define_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(Vec<Value>) -> Result<Value, String> + 'static,
+    {
+        let native = NativeFunction::new(name, arity, function);
+        self.globals.insert(name.to_string(), Value::NativeFunction(Rc::new(native)));
+    }
+
+    // Runtime errors raised since the last call, oldest first.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    pub fn interpret(&mut self, function: Rc<BytecodeFunction>) {
+        let closure = Rc::new(Closure {
+            function,
+            upvalues: Vec::new(),
+        });
+
+        self.stack.push(Value::Closure(Rc::clone(&closure)));
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            slots: 0,
+        });
+
+        if let Err(message) = self.run() {
+            let span = self.current_span();
+            let trace = self.stack_trace();
+            self.diagnostics.push(Diagnostic::error(Phase::Runtime, span.get_line(), message).with_span(span).with_trace(trace));
+
+            self.stack.clear();
+            self.frames.clear();
+            self.open_upvalues.clear();
+        }
+    }
+
+    fn run(&mut self) -> Result<(), String> {
+        loop {
+            let byte = self.read_byte();
+            let op = match OpCode::from_byte(byte) {
+                Some(op) => op,
+                None => return Err(format!("Unknown opcode {}.", byte)),
+            };
+
+            match op {
+                OpCode::Constant => {
+                    let value = self.read_constant_value()?;
+                    self.stack.push(value);
+                },
+                OpCode::Nil => self.stack.push(Value::Nil),
+                OpCode::True => self.stack.push(Value::Boolean(true)),
+                OpCode::False => self.stack.push(Value::Boolean(false)),
+                OpCode::Pop => {
+                    self.pop();
+                },
+                OpCode::Duplicate => {
+                    let count = self.read_byte() as usize;
+                    let top = self.stack[self.stack.len() - count..].to_vec();
+                    self.stack.extend(top);
+                },
+                OpCode::SetBelow => {
+                    let distance = self.read_byte() as usize;
+                    let slot = self.stack.len() - 1 - distance;
+                    self.stack[slot] = self.peek(0).clone();
+                },
+                OpCode::GetLocal => {
+                    let slot = self.read_byte() as usize + self.frame().slots;
+                    self.stack.push(self.stack[slot].clone());
+                },
+                OpCode::SetLocal => {
+                    let slot = self.read_byte() as usize + self.frame().slots;
+                    self.stack[slot] = self.peek(0).clone();
+                },
+                OpCode::GetGlobal => {
+                    let name = self.read_string()?;
+                    match self.globals.get(&name) {
+                        Some(value) => self.stack.push(value.clone()),
+                        None => return Err(format!("{} is not defined!", name)),
+                    }
+                },
+                OpCode::DefineGlobal => {
+                    let name = self.read_string()?;
+                    let value = self.pop();
+                    self.globals.insert(name, value);
+                },
+                OpCode::SetGlobal => {
+                    let name = self.read_string()?;
+                    let value = self.peek(0).clone();
+                    match self.globals.get_mut(&name) {
+                        Some(global) => *global = value,
+                        None => return Err(format!("{} is not defined!", name)),
+                    }
+                },
+                OpCode::GetUpvalue => {
+                    let index = self.read_byte() as usize;
+                    let upvalue = Rc::clone(&self.frame().closure.upvalues[index]);
+                    let value = match &*upvalue.borrow() {
+                        Upvalue::Open(slot) => self.stack[*slot].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.stack.push(value);
+                },
+                OpCode::SetUpvalue => {
+                    let index = self.read_byte() as usize;
+                    let upvalue = Rc::clone(&self.frame().closure.upvalues[index]);
+                    let value = self.peek(0).clone();
+                    let mut upvalue = upvalue.borrow_mut();
+                    match &mut *upvalue {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    }
+                },
+                OpCode::Equal => {
+                    let (left, right) = self.pop_pair();
+                    let result = operators::is_equal(left, right)?;
+                    self.stack.push(Value::Boolean(result));
+                },
+                OpCode::NotEqual => {
+                    let (left, right) = self.pop_pair();
+                    let result = operators::is_equal(left, right)?;
+                    self.stack.push(Value::Boolean(!result));
+                },
+                OpCode::Greater => self.binary(operators::greater)?,
+                OpCode::GreaterEqual => self.binary(operators::greater_equal)?,
+                OpCode::Less => self.binary(operators::less)?,
+                OpCode::LessEqual => self.binary(operators::less_equal)?,
+                OpCode::Add => self.binary(operators::sum)?,
+                OpCode::Subtract => self.binary(operators::subtract)?,
+                OpCode::Multiply => self.binary(operators::multiply)?,
+                OpCode::Divide => self.binary(operators::divide)?,
+                OpCode::Modulo => self.binary(operators::modulo)?,
+                OpCode::FloorDivide => self.binary(operators::floor_divide)?,
+                OpCode::Power => self.binary(operators::power)?,
+                OpCode::Not => {
+                    let value = self.pop();
+                    self.stack.push(Value::Boolean(!operators::is_truthy(&value)));
+                },
+                OpCode::Negate => {
+                    let value = self.pop();
+                    self.stack.push(operators::negate(value)?);
+                },
+                OpCode::BuildList => {
+                    let count = self.read_u16() as usize;
+                    let elements = self.stack.split_off(self.stack.len() - count);
+                    self.stack.push(Value::list(elements));
+                },
+                OpCode::BuildMap => {
+                    let count = self.read_u16() as usize;
+                    let entries = self.stack.split_off(self.stack.len() - 2 * count);
+
+                    let mut map = Map::new();
+                    for pair in entries.chunks(2) {
+                        map.insert(pair[0].clone(), pair[1].clone())?;
+                    }
+
+                    self.stack.push(Value::map(map));
+                },
+                OpCode::GetIndex => {
+                    let (object, index) = self.pop_pair();
+                    self.stack.push(operators::get_index(object, index)?);
+                },
+                OpCode::SetIndex => {
+                    let value = self.pop();
+                    let (object, index) = self.pop_pair();
+                    self.stack.push(operators::set_index(object, index, value)?);
+                },
+                OpCode::Print => {
+                    println!("{}", self.pop());
+                },
+                OpCode::Jump => {
+                    let offset = self.read_u16() as usize;
+                    self.frame_mut().ip += offset;
+                },
+                OpCode::JumpIfFalse => {
+                    let offset = self.read_u16() as usize;
+                    if !operators::is_truthy(self.peek(0)) {
+                        self.frame_mut().ip += offset;
+                    }
+                },
+                OpCode::Loop => {
+                    let offset = self.read_u16() as usize;
+                    self.frame_mut().ip -= offset;
+                },
+                OpCode::Call => {
+                    let argument_count = self.read_byte() as usize;
+                    self.call_value(argument_count)?;
+                },
+                OpCode::Closure => {
+                    let function = match self.read_constant() {
+                        Constant::Function(function) => function,
+                        _ => return Err("Closure operand is not a function.".to_string()),
+                    };
+
+                    let mut upvalues = Vec::with_capacity(function.upvalue_count);
+                    for _ in 0..function.upvalue_count {
+                        let is_local = self.read_byte() == 1;
+                        let index = self.read_byte() as usize;
+
+                        if is_local {
+                            let slot = self.frame().slots + index;
+                            upvalues.push(self.capture_upvalue(slot));
+                        }
+                        else {
+                            upvalues.push(Rc::clone(&self.frame().closure.upvalues[index]));
+                        }
+                    }
+
+                    self.stack.push(Value::Closure(Rc::new(Closure { function, upvalues })));
+                },
+                OpCode::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                },
+                OpCode::Return => {
+                    let result = self.pop();
+                    let frame = self.frames.pop().unwrap();
+                    self.close_upvalues(frame.slots);
+                    self.stack.truncate(frame.slots);
+
+                    if self.frames.is_empty() {
+                        return Ok(());
+                    }
+
+                    self.stack.push(result);
+                },
+                OpCode::Class => {
+                    let name = self.read_string()?;
+                    let class = BytecodeClass { name, methods: RefCell::new(HashMap::new()) };
+                    self.stack.push(Value::BytecodeClass(Rc::new(class)));
+                },
+                OpCode::Inherit => {
+                    let superclass = match self.peek(1) {
+                        Value::BytecodeClass(superclass) => Rc::clone(superclass),
+                        _ => return Err("Superclass must be a class.".to_string()),
+                    };
+
+                    if let Value::BytecodeClass(subclass) = self.pop() {
+                        let methods = superclass.methods.borrow().clone();
+                        subclass.methods.borrow_mut().extend(methods);
+                    }
+                },
+                OpCode::Method => {
+                    let name = self.read_string()?;
+                    let method = self.pop();
+
+                    if let (Value::BytecodeClass(class), Value::Closure(method)) = (self.peek(0), method) {
+                        class.methods.borrow_mut().insert(name, method);
+                    }
+                },
+                OpCode::GetProperty => {
+                    let name = self.read_string()?;
+                    let instance = match self.peek(0) {
+                        Value::BytecodeInstance(instance) => Rc::clone(instance),
+                        _ => return Err("Only instances have properties.".to_string()),
+                    };
+
+                    let field = instance.borrow().fields.get(&name).cloned();
+                    match field {
+                        Some(value) => {
+                            self.pop();
+                            self.stack.push(value);
+                        },
+                        None => self.bind_method(instance.borrow().get_class(), &name)?,
+                    }
+                },
+                OpCode::SetProperty => {
+                    let name = self.read_string()?;
+                    let instance = match self.peek(1) {
+                        Value::BytecodeInstance(instance) => Rc::clone(instance),
+                        _ => return Err("Only instances have fields.".to_string()),
+                    };
+
+                    let value = self.pop();
+                    instance.borrow_mut().fields.insert(name, value.clone());
+                    self.pop();
+                    self.stack.push(value);
+                },
+                OpCode::GetSuper => {
+                    let name = self.read_string()?;
+
+                    if let Value::BytecodeClass(superclass) = self.pop() {
+                        self.bind_method(superclass, &name)?;
+                    }
+                },
+                OpCode::Invoke => {
+                    let name = self.read_string()?;
+                    let argument_count = self.read_byte() as usize;
+                    self.invoke(&name, argument_count)?;
+                },
+                OpCode::SuperInvoke => {
+                    let name = self.read_string()?;
+                    let argument_count = self.read_byte() as usize;
+
+                    if let Value::BytecodeClass(superclass) = self.pop() {
+                        self.invoke_from_class(superclass, &name, argument_count)?;
+                    }
+                },
+            }
+        }
+    }
+
+    fn call_value(&mut self, argument_count: usize) -> Result<(), String> {
+        let callee = self.peek(argument_count).clone();
+
+        match callee {
+            Value::Closure(closure) => self.call(closure, argument_count),
+            Value::BoundMethod(bound) => {
+                let slot = self.stack.len() - argument_count - 1;
+                self.stack[slot] = bound.receiver.clone();
+
+                self.call(Rc::clone(&bound.method), argument_count)
+            },
+            Value::BytecodeClass(class) => {
+                let slot = self.stack.len() - argument_count - 1;
+                let instance = BytecodeInstance { class: Rc::clone(&class), fields: HashMap::new() };
+                self.stack[slot] = Value::BytecodeInstance(Rc::new(RefCell::new(instance)));
+
+                match class.find_method("init") {
+                    Some(initializer) => self.call(initializer, argument_count),
+                    None if argument_count != 0 => Err(format!("Expected 0 arguments but got {}.", argument_count)),
+                    None => Ok(()),
+                }
+            },
+            Value::NativeFunction(native) => {
+                if argument_count != native.arity() {
+                    return Err(format!("Expected {} arguments but got {}.", native.arity(), argument_count));
+                }
+
+                let arguments = self.stack.split_off(self.stack.len() - argument_count);
+                self.pop();
+
+                let result = native.call(arguments)?;
+                self.stack.push(result);
+
+                Ok(())
+            },
+            _ => Err("Can only call functions and classes.".to_string()),
+        }
+    }
+
+    fn call(&mut self, closure: Rc<Closure>, argument_count: usize) -> Result<(), String> {
+        if argument_count != closure.function.arity {
+            return Err(format!("Expected {} arguments but got {}.", closure.function.arity, argument_count));
+        }
+
+        if self.frames.len() == FRAMES_MAX {
+            return Err("Stack overflow.".to_string());
+        }
+
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            slots: self.stack.len() - argument_count - 1,
+        });
+
+        Ok(())
+    }
+
+    // `receiver.name(arguments)` without building a bound method. A field holding a
+    // function is called like any other value.
+    fn invoke(&mut self, name: &str, argument_count: usize) -> Result<(), String> {
+        let instance = match self.peek(argument_count) {
+            Value::BytecodeInstance(instance) => Rc::clone(instance),
+            _ => return Err("Only instances have properties.".to_string()),
+        };
+
+        let field = instance.borrow().fields.get(name).cloned();
+        if let Some(value) = field {
+            let slot = self.stack.len() - argument_count - 1;
+            self.stack[slot] = value;
+
+            return self.call_value(argument_count);
+        }
+
+        let class = instance.borrow().get_class();
+        self.invoke_from_class(class, name, argument_count)
+    }
+
+    fn invoke_from_class(&mut self, class: Rc<BytecodeClass>, name: &str, argument_count: usize) -> Result<(), String> {
+        match class.find_method(name) {
+            Some(method) => self.call(method, argument_count),
+            None => Err(format!("Undefined property '{}'.", name)),
+        }
+    }
+
+    // Replaces the instance on top of the stack with its method `name` bound to it.
+    fn bind_method(&mut self, class: Rc<BytecodeClass>, name: &str) -> Result<(), String> {
+        let method = match class.find_method(name) {
+            Some(method) => method,
+            None => return Err(format!("Undefined property '{}'.", name)),
+        };
+
+        let receiver = self.pop();
+        self.stack.push(Value::BoundMethod(Rc::new(BoundMethod { receiver, method })));
+
+        Ok(())
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+        for upvalue in &self.open_upvalues {
+            if let Upvalue::Open(open_slot) = *upvalue.borrow() {
+                if open_slot == slot {
+                    return Rc::clone(upvalue);
+                }
+            }
+        }
+
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        self.open_upvalues.push(Rc::clone(&upvalue));
+
+        upvalue
+    }
+
+    // Moves every captured variable living at `last` or above off the stack and into its upvalue.
+    fn close_upvalues(&mut self, last: usize) {
+        let stack = &self.stack;
+
+        self.open_upvalues.retain(|upvalue| {
+            let slot = match *upvalue.borrow() {
+                Upvalue::Open(slot) => slot,
+                Upvalue::Closed(_) => return false,
+            };
+
+            if slot < last {
+                return true;
+            }
+
+            *upvalue.borrow_mut() = Upvalue::Closed(stack[slot].clone());
+            false
+        });
+    }
+
+    fn binary(&mut self, operation: fn(Value, Value) -> Result<Value, String>) -> Result<(), String> {
+        let (left, right) = self.pop_pair();
+        let result = operation(left, right)?;
+        self.stack.push(result);
+
+        Ok(())
+    }
+
+    fn pop_pair(&mut self) -> (Value, Value) {
+        let right = self.pop();
+        let left = self.pop();
+
+        (left, right)
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap_or(Value::Nil)
+    }
+
+    fn peek(&self, distance: usize) -> &Value {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    fn frame(&self) -> &CallFrame {
+        self.frames.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let frame = self.frame_mut();
+        let byte = frame.closure.function.chunk.code[frame.ip];
+        frame.ip += 1;
+
+        byte
+    }
+
+    fn read_u16(&mut self) -> u16 {
+        let frame = self.frame_mut();
+        let value = frame.closure.function.chunk.read_u16(frame.ip);
+        frame.ip += 2;
+
+        value
+    }
+
+    fn read_constant(&mut self) -> Constant {
+        let index = self.read_u16() as usize;
+        self.frame().closure.function.chunk.constants[index].clone()
+    }
+
+    fn read_constant_value(&mut self) -> Result<Value, String> {
+        match self.read_constant() {
+            Constant::Number(number) => Ok(Value::Literal(LiteralPossibleValues::DoubleValue(number))),
+            Constant::String(string) => Ok(Value::Literal(LiteralPossibleValues::StringValue(string))),
+            Constant::Function(_) => Err("Functions must be loaded as closures.".to_string()),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        match self.read_constant() {
+            Constant::String(string) => Ok(string),
+            _ => Err("Expected a name constant.".to_string()),
+        }
+    }
+
+    // Every called function, innermost first, with the line its caller was executing.
+    fn stack_trace(&self) -> Vec<StackFrame> {
+        let mut trace = Vec::new();
+
+        for (index, frame) in self.frames.iter().enumerate().skip(1).rev() {
+            let caller = &self.frames[index - 1];
+            let line = caller.closure.function.chunk.get_line(caller.ip.saturating_sub(1));
+            trace.push(StackFrame::new(frame.closure.function.name.clone(), line));
+        }
+
+        trace
+    }
+
+    fn current_span(&self) -> Span {
+        match self.frames.last() {
+            Some(frame) => frame.closure.function.chunk.get_span(frame.ip.saturating_sub(1)),
+            None => Span::default(),
+        }
+    }
+}